@@ -0,0 +1,210 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    client::{Page, Validators},
+    types::Error,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub url_path: String,
+    pub body: String,
+    /// unix timestamp in seconds
+    pub fetched_at: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl CacheEntry {
+    pub fn new(url_path: &str, page: Page) -> Self {
+        Self {
+            url_path: url_path.to_string(),
+            body: page.body,
+            fetched_at: now(),
+            etag: page.validators.etag,
+            last_modified: page.validators.last_modified,
+        }
+    }
+
+    pub fn validators(&self) -> Validators {
+        Validators {
+            etag: self.etag.clone(),
+            last_modified: self.last_modified.clone(),
+        }
+    }
+
+    /// marks a cached page as fetched right now, after the server confirmed it is unchanged
+    pub fn touch(&mut self) {
+        self.fetched_at = now();
+    }
+}
+
+/// Disk-backed page cache, one json file per url path.
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+        }
+    }
+
+    pub fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        now().saturating_sub(entry.fetched_at) < self.ttl.as_secs()
+    }
+
+    /// unreadable or corrupted entries are treated as a miss
+    pub async fn get(&self, url_path: &str) -> Option<CacheEntry> {
+        let bytes = fs::read(self.entry_path(url_path)).await.ok()?;
+        serde_json::from_slice(&bytes)
+            .inspect_err(|e| debug!("ignoring corrupted cache entry of {}: {}", url_path, e))
+            .ok()
+    }
+
+    pub async fn put(&self, entry: &CacheEntry) -> Result<(), Error> {
+        fs::create_dir_all(&self.dir).await?;
+        let path = self.entry_path(&entry.url_path);
+        // write then rename, a crash mid-write must not leave a truncated entry behind
+        let tmp = path.with_extension("json.tmp");
+        let bytes = serde_json::to_vec(entry)
+            .map_err(|e| Error::Io(format!("{} on {}", e, &entry.url_path)))?;
+        fs::write(&tmp, bytes).await?;
+        fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    /// same as [`DiskCache::put`], failures are only logged since the page itself is fine
+    pub async fn store(&self, entry: &CacheEntry) {
+        if let Err(e) = self.put(entry).await {
+            error!("Failed caching {}: {}", &entry.url_path, e);
+        }
+    }
+
+    fn entry_path(&self, url_path: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", file_name(url_path)))
+    }
+}
+
+/// longest encoded name kept as it is, leaves room for the extension under the 255 bytes limit
+const MAX_FILE_NAME: usize = 200;
+
+/// Filesystem safe file name of an url path.
+///
+/// Short paths are percent-encoded and stay readable, names longer than [`MAX_FILE_NAME`] are
+/// cut and suffixed with a hash of the whole path.
+pub(crate) fn file_name(url_path: &str) -> String {
+    let encoded: String = url_path
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    if encoded.len() <= MAX_FILE_NAME {
+        return encoded;
+    }
+    let hash = format!("{:016x}", fnv1a(url_path.as_bytes()));
+    // encoded names are ascii, any byte index is a char boundary
+    format!("{}~{}", &encoded[..MAX_FILE_NAME - hash.len() - 1], hash)
+}
+
+/// 64-bit FNV-1a, stable across builds unlike the std hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+pub mod mocks {
+    use std::path::PathBuf;
+
+    pub fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("opdb-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::client::{Page, Validators};
+
+    use super::{file_name, mocks::temp_dir, CacheEntry, DiskCache};
+
+    #[test]
+    fn file_name_is_filesystem_safe() {
+        assert_eq!(file_name("/wiki/Zoan"), "%2Fwiki%2FZoan");
+        assert_eq!(
+            file_name("/wiki/Category:Ships?from=B"),
+            "%2Fwiki%2FCategory%3AShips%3Ffrom%3DB"
+        );
+        assert_ne!(file_name("/wiki/a:b"), file_name("/wiki/a_b"));
+    }
+
+    #[test]
+    fn long_file_names_are_hashed() {
+        let long = format!("/wiki/{}", "ゴムゴムの実".repeat(40));
+        let other = format!("{}?from=B", long);
+
+        let name = file_name(&long);
+        assert_eq!(name.len(), 200);
+        assert!(name.starts_with("%2Fwiki%2F"));
+        assert_eq!(name, file_name(&long));
+        assert_ne!(name, file_name(&other));
+        assert_eq!(file_name(&other).len(), 200);
+    }
+
+    #[tokio::test]
+    async fn disk_cache_roundtrip() {
+        let dir = temp_dir("disk-cache-roundtrip");
+        let cache = DiskCache::new(&dir, Duration::from_secs(60));
+        assert!(cache.get("/wiki/Zoan").await.is_none());
+
+        let entry = CacheEntry::new(
+            "/wiki/Zoan",
+            Page {
+                body: "<html>zoan</html>".to_string(),
+                validators: Validators {
+                    etag: Some("\"v1\"".to_string()),
+                    last_modified: None,
+                },
+            },
+        );
+        cache.put(&entry).await.unwrap();
+
+        let cached = cache.get("/wiki/Zoan").await.unwrap();
+        assert_eq!(cached, entry);
+        assert!(cache.is_fresh(&cached));
+        assert_eq!(cached.validators().etag.as_deref(), Some("\"v1\""));
+
+        let expired = DiskCache::new(&dir, Duration::ZERO);
+        assert!(!expired.is_fresh(&cached));
+
+        std::fs::write(dir.join("%2Fwiki%2FLogia.json"), "{ not json").unwrap();
+        assert!(cache.get("/wiki/Logia").await.is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
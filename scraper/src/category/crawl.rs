@@ -0,0 +1,412 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    future::Future,
+};
+
+use futures::{stream, StreamExt};
+use log::{debug, error, info, warn};
+use regex::Regex;
+use tokio::task::JoinSet;
+
+use crate::{mediawiki::models::path_to_title, types::Error};
+
+use super::UrlCrawler;
+
+/// Limits of a nested category crawl.
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    /// subcategories deeper than this are not crawled, the root category is at depth 0
+    pub max_depth: Option<usize>,
+    /// only subcategories whose title matches are crawled
+    pub include: Option<Regex>,
+    /// subcategories whose title matches are skipped
+    pub exclude: Option<Regex>,
+    /// the crawl stops once this many page urls were collected
+    pub max_urls: Option<usize>,
+    /// categories fetched at once within one depth level
+    pub concurrency: usize,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            include: None,
+            exclude: None,
+            max_urls: None,
+            concurrency: 8,
+        }
+    }
+}
+
+impl CrawlOptions {
+    fn within_depth(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth <= max)
+    }
+
+    fn accepts(&self, category: &str) -> bool {
+        let title = path_to_title(category);
+        self.include.as_ref().is_none_or(|re| re.is_match(&title))
+            && !self.exclude.as_ref().is_some_and(|re| re.is_match(&title))
+    }
+}
+
+/// Page url to the paths of the categories it was directly listed under, see `Sea::from_categories`.
+pub type CategoryMembership = BTreeMap<String, BTreeSet<String>>;
+
+/// Category name of a category path, e.g. `Grand Line Pirate Crews`.
+pub fn category_name(path: &str) -> String {
+    let title = path_to_title(path);
+    match title.strip_prefix("Category:") {
+        Some(name) => name.to_string(),
+        None => title,
+    }
+}
+
+/// Pages found by a nested category crawl.
+#[derive(Debug, Default)]
+pub struct CrawlResult {
+    pub membership: CategoryMembership,
+    pub errors: Vec<Error>,
+    /// the url budget ran out before every category was crawled
+    pub truncated: bool,
+}
+
+impl CrawlResult {
+    /// Pages of the crawl, failing in `strict` mode if any category could not be crawled.
+    pub fn into_membership(self, strict: bool) -> Result<CategoryMembership, Error> {
+        if self.errors.is_empty() {
+            Ok(self.membership)
+        } else if strict {
+            self.errors.iter().for_each(|err| error!("{:?}", err));
+            Err(Error::Request(format!(
+                "%{} Error happened while crawling categories",
+                self.errors.len()
+            )))
+        } else {
+            info!("non-strict mode category crawler having some errors");
+            self.errors.iter().for_each(|err| info!("{:?}", err));
+            Ok(self.membership)
+        }
+    }
+
+    fn is_full(&self, options: &CrawlOptions) -> bool {
+        options
+            .max_urls
+            .is_some_and(|max| self.membership.len() >= max)
+    }
+}
+
+/// Parses every page listed under `root` or its subcategories, sorted.
+///
+/// The crawl is not strict and pages failing to parse are logged and skipped: with thousands
+/// of nested categories, a few failing ones should not lose the others.
+pub async fn parse_members<C, T, F, Fut>(
+    crawler: &C,
+    root: &str,
+    options: &CrawlOptions,
+    kind: &str,
+    parse: F,
+) -> Result<Vec<T>, Error>
+where
+    C: UrlCrawler + ?Sized,
+    T: Ord + Send + 'static,
+    F: Fn(String, Vec<String>) -> Fut,
+    Fut: Future<Output = Result<T, Error>> + Send + 'static,
+{
+    info!("crawling {} categories", kind);
+    let membership = crawler.get_nested_href(root, options, false).await?;
+
+    let mut tasks = JoinSet::new();
+    for (url, categories) in membership {
+        let categories = categories.iter().map(|c| category_name(c)).collect();
+        let task = parse(url.clone(), categories);
+        tasks.spawn(async move { (url, task.await) });
+    }
+    info!("collecting {} pages", kind);
+    let mut pages = vec![];
+    while let Some(res) = tasks.join_next().await {
+        match res {
+            Ok((_, Ok(page))) => pages.push(page),
+            Ok((url, Err(e))) if e.is_not_found() => {
+                warn!("{} page {} no longer exists: {}", kind, url, e)
+            }
+            Ok((url, Err(e))) => error!("Error parsing {} detail at {}: {}", kind, url, e),
+            Err(e) => error!("JoinSet error {}", e),
+        }
+    }
+    pages.sort();
+    Ok(pages)
+}
+
+fn is_category(path: &str) -> bool {
+    path.contains("Category:")
+}
+
+/// BFS crawling, every category of a depth level is fetched concurrently.
+pub(super) async fn breadth_first<C: UrlCrawler + ?Sized>(
+    crawler: &C,
+    root: &str,
+    options: &CrawlOptions,
+) -> CrawlResult {
+    let mut result = CrawlResult::default();
+    let mut visited = HashSet::from([root.to_string()]);
+    let mut level = vec![root.to_string()];
+    let mut depth = 0;
+
+    while !level.is_empty() {
+        debug!("BFS crawling {} categories at depth {}", level.len(), depth);
+        let mut fetched = stream::iter(level)
+            .map(|category| async move {
+                let members = crawler.get_href(&category).await;
+                (category, members)
+            })
+            .buffer_unordered(options.concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+        // completion order is arbitrary, keep the budget cut deterministic
+        fetched.sort_by(|a, b| a.0.cmp(&b.0));
+        depth += 1;
+
+        let mut next_level = vec![];
+        for (category, members) in fetched {
+            let members = match members {
+                Ok(members) => members,
+                Err(e) => {
+                    result.errors.push(e);
+                    continue;
+                }
+            };
+            for member in members {
+                if is_category(&member) {
+                    if options.within_depth(depth)
+                        && options.accepts(&member)
+                        && visited.insert(member.clone())
+                    {
+                        next_level.push(member);
+                    }
+                } else if let Some(categories) = result.membership.get_mut(&member) {
+                    categories.insert(category.clone());
+                } else if result.is_full(options) {
+                    result.truncated = true;
+                } else {
+                    result
+                        .membership
+                        .insert(member, BTreeSet::from([category.clone()]));
+                }
+            }
+        }
+        if result.truncated || result.is_full(options) {
+            if !next_level.is_empty() {
+                result.truncated = true;
+            }
+            break;
+        }
+        level = next_level;
+    }
+    if result.truncated {
+        warn!(
+            "{}: url budget of {:?} reached, the crawl is incomplete",
+            root, options.max_urls
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use async_trait::async_trait;
+    use regex::Regex;
+
+    use crate::{category::UrlCrawler, types::Error};
+
+    use super::{category_name, parse_members, CrawlOptions};
+
+    struct TreeCrawler(HashMap<&'static str, Vec<&'static str>>);
+
+    #[async_trait]
+    impl UrlCrawler for TreeCrawler {
+        async fn get_href(&self, path: &str) -> Result<Vec<String>, Error> {
+            self.0
+                .get(path)
+                .map(|members| members.iter().map(|m| m.to_string()).collect())
+                .ok_or_else(|| Error::HttpStatus {
+                    url: path.to_string(),
+                    status: 404,
+                    retry_after: None,
+                })
+        }
+    }
+
+    fn pirate_tree() -> TreeCrawler {
+        TreeCrawler(HashMap::from([
+            (
+                "/wiki/Category:Pirate_Groups",
+                vec![
+                    "/wiki/Category:Grand_Line_Pirate_Crews",
+                    "/wiki/Category:East_Blue_Pirate_Crews",
+                    "/wiki/Roger_Pirates",
+                ],
+            ),
+            (
+                "/wiki/Category:Grand_Line_Pirate_Crews",
+                vec![
+                    "/wiki/Fallen_Monk_Pirates",
+                    "/wiki/Roger_Pirates",
+                    "/wiki/Category:Non-Canon_Grand_Line_Pirate_Crews",
+                    "/wiki/Category:Pirate_Groups",
+                ],
+            ),
+            (
+                "/wiki/Category:East_Blue_Pirate_Crews",
+                vec!["/wiki/Buggy_Pirates", "/wiki/Roger_Pirates"],
+            ),
+            (
+                "/wiki/Category:Non-Canon_Grand_Line_Pirate_Crews",
+                vec!["/wiki/Gasparde_Pirates"],
+            ),
+        ]))
+    }
+
+    fn urls(result: &super::CrawlResult) -> Vec<&str> {
+        result.membership.keys().map(String::as_str).collect()
+    }
+
+    #[tokio::test]
+    async fn crawl_records_membership() {
+        let result = pirate_tree()
+            .crawl("/wiki/Category:Pirate_Groups", &CrawlOptions::default())
+            .await;
+        assert!(result.errors.is_empty());
+        assert!(!result.truncated);
+        assert_eq!(
+            urls(&result),
+            vec![
+                "/wiki/Buggy_Pirates",
+                "/wiki/Fallen_Monk_Pirates",
+                "/wiki/Gasparde_Pirates",
+                "/wiki/Roger_Pirates"
+            ]
+        );
+        assert_eq!(
+            result.membership["/wiki/Roger_Pirates"],
+            BTreeSet::from([
+                "/wiki/Category:East_Blue_Pirate_Crews".to_string(),
+                "/wiki/Category:Grand_Line_Pirate_Crews".to_string(),
+                "/wiki/Category:Pirate_Groups".to_string(),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn crawl_respects_depth_and_filters() {
+        let crawler = pirate_tree();
+        let shallow = CrawlOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let result = crawler
+            .crawl("/wiki/Category:Pirate_Groups", &shallow)
+            .await;
+        assert!(!result.membership.contains_key("/wiki/Gasparde_Pirates"));
+        assert_eq!(result.membership.len(), 3);
+
+        let root_only = CrawlOptions {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        let result = crawler
+            .crawl("/wiki/Category:Pirate_Groups", &root_only)
+            .await;
+        assert_eq!(urls(&result), vec!["/wiki/Roger_Pirates"]);
+
+        let canon_grand_line = CrawlOptions {
+            include: Some(Regex::new("Grand Line").unwrap()),
+            exclude: Some(Regex::new("^Category:Non-Canon").unwrap()),
+            ..Default::default()
+        };
+        let result = crawler
+            .crawl("/wiki/Category:Pirate_Groups", &canon_grand_line)
+            .await;
+        assert_eq!(
+            urls(&result),
+            vec!["/wiki/Fallen_Monk_Pirates", "/wiki/Roger_Pirates"]
+        );
+    }
+
+    #[tokio::test]
+    async fn crawl_stops_at_url_budget() {
+        let budget = CrawlOptions {
+            max_urls: Some(2),
+            ..Default::default()
+        };
+        let result = pirate_tree()
+            .crawl("/wiki/Category:Pirate_Groups", &budget)
+            .await;
+        assert!(result.truncated);
+        assert_eq!(
+            urls(&result),
+            vec!["/wiki/Buggy_Pirates", "/wiki/Roger_Pirates"]
+        );
+    }
+
+    #[tokio::test]
+    async fn crawl_collects_errors() {
+        let mut crawler = pirate_tree();
+        crawler.0.remove("/wiki/Category:East_Blue_Pirate_Crews");
+        let result = crawler
+            .crawl("/wiki/Category:Pirate_Groups", &CrawlOptions::default())
+            .await;
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].is_not_found());
+        assert_eq!(result.membership.len(), 3);
+        assert!(result.into_membership(true).is_err());
+    }
+
+    #[tokio::test]
+    async fn parse_members_skips_failures() {
+        let mut crawler = pirate_tree();
+        crawler.0.remove("/wiki/Category:East_Blue_Pirate_Crews");
+        let pages = parse_members(
+            &crawler,
+            "/wiki/Category:Pirate_Groups",
+            &CrawlOptions::default(),
+            "pirate",
+            |url, categories| async move {
+                match url.as_str() {
+                    "/wiki/Gasparde_Pirates" => Err(Error::Request(url)),
+                    _ => Ok((url, categories)),
+                }
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            pages,
+            vec![
+                (
+                    "/wiki/Fallen_Monk_Pirates".to_string(),
+                    vec!["Grand Line Pirate Crews".to_string()]
+                ),
+                (
+                    "/wiki/Roger_Pirates".to_string(),
+                    vec![
+                        "Grand Line Pirate Crews".to_string(),
+                        "Pirate Groups".to_string()
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn category_name_from_path() {
+        assert_eq!(
+            category_name("/wiki/Category:Non-Canon_Grand_Line_Pirate_Crews"),
+            "Non-Canon Grand Line Pirate Crews"
+        );
+        assert_eq!(category_name("/wiki/Pirate_Groups"), "Pirate Groups");
+    }
+}
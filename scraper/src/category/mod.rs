@@ -1,2 +1,4 @@
+pub mod crawl;
 pub mod scraper;
+pub use crawl::*;
 pub use scraper::*;
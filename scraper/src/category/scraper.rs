@@ -1,16 +1,30 @@
 use std::collections::HashSet;
 
 use async_trait::async_trait;
-use log::{debug, error, info};
+use log::info;
 use scraper::Html;
 
 use crate::{fetcher::HtmlFetcher, types::Error, utils};
 
+use super::{crawl, CategoryMembership, CrawlOptions, CrawlResult};
+
 #[async_trait]
-pub trait UrlCrawler {
+pub trait UrlCrawler: Send + Sync {
     async fn get_href(&self, path: &str) -> Result<Vec<String>, Error>;
 
-    async fn get_nested_href(&self, path: &str, strict: bool) -> Result<Vec<String>, Error>;
+    /// Crawls `path` and its subcategories within the limits of `options`.
+    async fn crawl(&self, path: &str, options: &CrawlOptions) -> CrawlResult {
+        crawl::breadth_first(self, path, options).await
+    }
+
+    async fn get_nested_href(
+        &self,
+        path: &str,
+        options: &CrawlOptions,
+        strict: bool,
+    ) -> Result<CategoryMembership, Error> {
+        self.crawl(path, options).await.into_membership(strict)
+    }
 }
 
 #[derive(Debug)]
@@ -18,90 +32,62 @@ pub struct CategoryScraper {
     fetcher: HtmlFetcher,
 }
 
+const MEMBER_SELECTOR: &str = "li.category-page__member a.category-page__member-link";
+
 impl CategoryScraper {
     pub fn new(fetcher: HtmlFetcher) -> Self {
         Self { fetcher }
     }
+
+    /// Members of a single category, following the pagination links up to the last page.
+    async fn get_members(&self, path: &str) -> Result<Vec<String>, Error> {
+        let next_selector = utils::parse_selector("a.category-page__pagination-next")?;
+        let mut members = vec![];
+        let mut pages = HashSet::new();
+        let mut next_page = Some(path.to_string());
+
+        while let Some(page) = next_page.take() {
+            // guard against a pagination link pointing back to an already visited page
+            if !pages.insert(page.clone()) {
+                break;
+            }
+            let html = self.fetcher.fetch_only(&page).await?;
+            let doc = Html::parse_document(&html);
+            members.extend(utils::extract_all_href(
+                &doc.root_element(),
+                MEMBER_SELECTOR,
+            )?);
+            next_page = doc
+                .select(&next_selector)
+                .next()
+                .and_then(|a| a.value().attr("href"))
+                .map(utils::to_url_path);
+        }
+        info!(
+            "{}: {} members on {} page(s)",
+            path,
+            members.len(),
+            pages.len()
+        );
+        Ok(members)
+    }
 }
 
 #[async_trait]
 #[cfg_attr(test, mockall::automock)]
 impl UrlCrawler for CategoryScraper {
     async fn get_href(&self, path: &str) -> Result<Vec<String>, Error> {
-        let html = self.fetcher.fetch_only(path).await?;
-        let doc = Html::parse_document(&html);
-
-        let links = utils::extract_all_href(
-            &doc.root_element(),
-            "li.category-page__member a.category-page__member-link",
-        )?;
-        // info!("links: {:?}", links);
-        Ok(links)
-    }
-
-    /// DFS crawling
-    async fn get_nested_href(&self, path: &str, strict: bool) -> Result<Vec<String>, Error> {
-        let root = path.to_string();
-
-        let mut visited = HashSet::new();
-        let mut stack = vec![root];
-        let mut urls = Vec::<String>::new();
-        let mut err_collection = vec![];
-
-        while !stack.is_empty() {
-            let next_path = stack.pop().unwrap();
-            if visited.contains(&next_path) {
-                continue;
-            }
-            debug!("DFS crawling on {:?}", next_path);
-            visited.insert(next_path.clone());
-
-            let html = self.fetcher.fetch_only(&next_path).await;
-            if html.is_err() {
-                err_collection.push(html.err().unwrap());
-                continue;
-            }
-            let doc = Html::parse_fragment(&html.unwrap());
-            let selector =
-                &utils::parse_selector("li.category-page__member a.category-page__member-link")?;
-            for a in doc
-                .select(selector)
-                .filter_map(|e| e.value().attr("href"))
-                .map(String::from)
-            {
-                if visited.contains(&a) {
-                    continue;
-                }
-
-                if a.contains("Category:") {
-                    stack.push(a);
-                } else {
-                    visited.insert(a.clone());
-                    urls.push(a);
-                }
-            }
-        }
-        if err_collection.is_empty() {
-            Ok(urls)
-        } else if strict {
-            err_collection.iter().for_each(|err| error!("{:?}", err));
-            Err(Error::RequestError(format!(
-                "%{} Error happened while crawling categories",
-                err_collection.len()
-            )))
-        } else {
-            info!("non-strict mode category crawler having some errors");
-            err_collection.iter().for_each(|err| info!("{:?}", err));
-            Ok(urls)
-        }
+        self.get_members(path).await
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+
     use crate::{
-        category::{CategoryScraper, UrlCrawler},
-        fetcher::{self, mocks::prepare_fetcher},
+        category::{CategoryScraper, CrawlOptions, UrlCrawler},
+        fetcher::mocks::prepare_fetcher,
     };
 
     #[tokio::test]
@@ -210,16 +196,28 @@ mod tests {
         ]);
         let crawler = CategoryScraper::new(fetcher);
         let result = crawler
-            .get_nested_href("/wiki/Category:Pirate_Crews_by_Sea", true)
+            .get_nested_href(
+                "/wiki/Category:Pirate_Crews_by_Sea",
+                &CrawlOptions::default(),
+                true,
+            )
             .await;
         assert!(result.is_err());
         let result = crawler
-            .get_nested_href("/wiki/Category:Pirate_Crews_by_Sea", false)
+            .get_nested_href(
+                "/wiki/Category:Pirate_Crews_by_Sea",
+                &CrawlOptions::default(),
+                false,
+            )
             .await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 2);
         let result = crawler
-            .get_nested_href("/wiki/Category:Some-categories", true)
+            .get_nested_href(
+                "/wiki/Category:Some-categories",
+                &CrawlOptions::default(),
+                true,
+            )
             .await;
         assert!(result.is_ok());
     }
@@ -282,10 +280,101 @@ mod tests {
         ]);
         let crawler = CategoryScraper::new(fetcher);
         let result = crawler
-            .get_nested_href("/wiki/Category:Pirate_Crews_by_Sea", true)
+            .get_nested_href(
+                "/wiki/Category:Pirate_Crews_by_Sea",
+                &CrawlOptions::default(),
+                true,
+            )
             .await
             .unwrap();
         assert_eq!(result.len(), 1);
-        assert_eq!(result[0], "/wiki/Fallen_Monk_Pirates");
+        assert_eq!(
+            result["/wiki/Fallen_Monk_Pirates"],
+            BTreeSet::from([
+                "/wiki/Category:Grand_Line_Pirate_Crews".to_string(),
+                "/wiki/Category:New_World_Pirate_Crews".to_string(),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn paginated_category_should_crawl_all_pages() {
+        let fetcher = prepare_fetcher([
+            (
+                "/wiki/Category:Ships".to_string(),
+                Ok(r##"
+    <div>
+        <ul>
+            <li class="category-page__member">
+                <a href="/wiki/Going_Merry" class="category-page__member-link" title="Going Merry">Going Merry</a>
+            </li>
+            <li class="category-page__member">
+                <a href="/wiki/Category:Marine_Ships" class="category-page__member-link" title="Category:Marine Ships">Category:Marine Ships</a>
+            </li>
+        </ul>
+        <div class="category-page__pagination">
+            <a href="https://onepiece.fandom.com/wiki/Category:Ships?from=M" class="category-page__pagination-next wds-button wds-is-secondary">Next page</a>
+        </div>
+    </div>"##.to_string()),
+            ),
+            (
+                "/wiki/Category:Ships?from=M".to_string(),
+                Ok(r##"
+    <div>
+        <ul>
+            <li class="category-page__member">
+                <a href="/wiki/Moby_Dick" class="category-page__member-link" title="Moby Dick">Moby Dick</a>
+            </li>
+        </ul>
+        <div class="category-page__pagination">
+            <a href="https://onepiece.fandom.com/wiki/Category:Ships" class="category-page__pagination-prev wds-button wds-is-secondary">Previous page</a>
+            <a href="https://onepiece.fandom.com/wiki/Category:Ships?from=T" class="category-page__pagination-next wds-button wds-is-secondary">Next page</a>
+        </div>
+    </div>"##.to_string()),
+            ),
+            (
+                "/wiki/Category:Ships?from=T".to_string(),
+                Ok(r##"
+    <div>
+        <ul>
+            <li class="category-page__member">
+                <a href="/wiki/Thousand_Sunny" class="category-page__member-link" title="Thousand Sunny">Thousand Sunny</a>
+            </li>
+        </ul>
+    </div>"##.to_string()),
+            ),
+            (
+                "/wiki/Category:Marine_Ships".to_string(),
+                Ok(r##"
+    <div>
+        <ul>
+            <li class="category-page__member">
+                <a href="/wiki/Moby_Dick" class="category-page__member-link" title="Moby Dick">Moby Dick</a>
+            </li>
+            <li class="category-page__member">
+                <a href="/wiki/Marine_Battleship" class="category-page__member-link" title="Marine Battleship">Marine Battleship</a>
+            </li>
+        </ul>
+    </div>"##.to_string()),
+            ),
+        ]);
+
+        let crawler = CategoryScraper::new(fetcher);
+        let result = crawler.get_href("/wiki/Category:Ships").await.unwrap();
+        assert_eq!(
+            result,
+            vec![
+                "/wiki/Going_Merry",
+                "/wiki/Category:Marine_Ships",
+                "/wiki/Moby_Dick",
+                "/wiki/Thousand_Sunny"
+            ]
+        );
+
+        let result = crawler
+            .get_nested_href("/wiki/Category:Ships", &CrawlOptions::default(), true)
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 4);
     }
 }
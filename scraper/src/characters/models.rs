@@ -0,0 +1,77 @@
+use serde::Serialize;
+use strum::{Display, EnumString};
+
+use crate::types::{NamedUrl, UrlTyped};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString, Serialize)]
+#[strum(ascii_case_insensitive)]
+pub enum CharacterStatus {
+    Alive,
+    Deceased,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Character {
+    pub name: String,
+    pub jp_name: String,
+    pub romanized_name: String,
+    pub epithet: Option<String>,
+    pub affiliations: Vec<NamedUrl>,
+    pub occupations: Vec<String>,
+    pub origin: Option<NamedUrl>,
+    pub status: CharacterStatus,
+    pub age: Option<String>,
+    pub birthday: Option<String>,
+    pub height: Option<String>,
+    pub bounty: Option<u64>,
+    pub devil_fruit: Option<NamedUrl>,
+    pub pic_url: String,
+    pub non_cannon: bool,
+    pub categories: Vec<String>,
+    url: String,
+}
+
+impl Character {
+    pub fn new(name: String, url: String, pic_url: String, non_cannon: bool) -> Self {
+        Self {
+            name,
+            url,
+            pic_url,
+            non_cannon,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_categories(mut self, categories: Vec<String>) -> Self {
+        self.categories = categories;
+        self
+    }
+}
+
+impl UrlTyped for Character {
+    fn get_path(&self) -> String {
+        self.url.clone()
+    }
+}
+
+impl Eq for Character {}
+
+impl PartialEq for Character {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url
+    }
+}
+
+impl Ord for Character {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.url.cmp(&other.url)
+    }
+}
+
+impl PartialOrd for Character {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,252 @@
+use std::{str::FromStr as _, sync::Arc};
+
+use scraper::Html;
+
+use crate::{
+    category::{self, CrawlOptions, UrlCrawler},
+    fetcher::HtmlFetcher,
+    types::{Error, ParseContext},
+    utils,
+};
+
+use super::models::{Character, CharacterStatus};
+
+pub struct CharacterScraper {
+    fetcher: HtmlFetcher,
+    category_crawler: Arc<dyn UrlCrawler>,
+    crawl_options: CrawlOptions,
+}
+
+impl CharacterScraper {
+    pub fn new(fetcher: HtmlFetcher, category_crawler: Arc<dyn UrlCrawler>) -> Self {
+        Self {
+            fetcher,
+            category_crawler,
+            crawl_options: CrawlOptions::default(),
+        }
+    }
+
+    pub fn with_crawl_options(mut self, options: CrawlOptions) -> Self {
+        self.crawl_options = options;
+        self
+    }
+
+    pub async fn scrape(&self) -> Result<Vec<Character>, Error> {
+        let fetcher = self.fetcher.clone();
+        category::parse_members(
+            self.category_crawler.as_ref(),
+            "/wiki/Category:Characters",
+            &self.crawl_options,
+            "character",
+            move |url, categories| {
+                let fetcher = fetcher.clone();
+                async move {
+                    parse_character_detail(fetcher, url)
+                        .await
+                        .map(|character| character.with_categories(categories))
+                }
+            },
+        )
+        .await
+    }
+}
+
+async fn parse_character_detail(
+    fetcher: HtmlFetcher,
+    character_url: String,
+) -> Result<Character, Error> {
+    let html = fetcher
+        .fetch(&character_url)
+        .await
+        .map(utils::cleanup_html)?;
+    let doc = Html::parse_document(&html);
+    let pic_url = utils::parse_picture_url(&doc)
+        .parse_field(&character_url, "picture")?
+        .first()
+        .cloned()
+        .unwrap_or_default();
+    let name = utils::parse_main_page_title(&doc).parse_field(&character_url, "title")?;
+    let non_cannon = utils::parse_is_non_cannon(&doc).parse_field(&character_url, "categories")?;
+    let mut character = Character::new(name, character_url, pic_url, non_cannon);
+    let stat_selector = utils::parse_selector("aside.portable-infobox .pi-data")?;
+    for el in doc.select(&stat_selector) {
+        if let Some(kind) = el.attr("data-source") {
+            match kind {
+                "jname" => character.jp_name = utils::parse_infobox_text(&el).unwrap_or_default(),
+                "rname" => {
+                    character.romanized_name = utils::parse_infobox_text(&el).unwrap_or_default()
+                }
+                "epithet" => {
+                    character.epithet = utils::parse_infobox_text(&el)
+                        .map(|t| t.trim_matches(['"', '“', '”']).trim().to_string())
+                }
+                "affiliation" => character
+                    .affiliations
+                    .extend(utils::parse_infobox_links(&el)),
+                "occupation" => character.occupations.extend(
+                    utils::parse_infobox_entries(&el)
+                        .into_iter()
+                        .map(|entry| entry.text)
+                        .filter(|text| !text.is_empty()),
+                ),
+                "origin" => character.origin = utils::parse_infobox_links(&el).into_iter().next(),
+                "status" => {
+                    character.status = utils::parse_infobox_entries(&el)
+                        .first()
+                        .and_then(|entry| CharacterStatus::from_str(&entry.text).ok())
+                        .unwrap_or_default()
+                }
+                "age" => character.age = utils::parse_infobox_text(&el),
+                "birth" | "birthday" => character.birthday = utils::parse_infobox_text(&el),
+                "height" => character.height = utils::parse_infobox_text(&el),
+                "bounty" => {
+                    character.bounty =
+                        utils::parse_infobox_text(&el).and_then(|text| utils::parse_berries(&text))
+                }
+                "dfname" | "dfename" if character.devil_fruit.is_none() => {
+                    character.devil_fruit = utils::parse_infobox_links(&el).into_iter().next()
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(character)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        category::CategoryScraper,
+        characters::{models::CharacterStatus, scraper::CharacterScraper},
+        fetcher::mocks::prepare_fetcher,
+        types::UrlTyped,
+    };
+
+    #[tokio::test]
+    async fn test_get() {
+        let fetcher = prepare_fetcher([
+            (
+                "/wiki/Category:Characters".to_string(),
+                Ok(r##"
+<div>
+    <ul>
+        <li class="category-page__member">
+            <a href="/wiki/Category:Supernovas" class="category-page__member-link" title="Category:Supernovas">Category:Supernovas</a>
+        </li>
+        <li class="category-page__member">
+            <a href="/wiki/Gol_D._Roger" class="category-page__member-link" title="Gol D. Roger">Gol D. Roger</a>
+        </li>
+    </ul>
+</div>"##
+                    .to_string()),
+            ),
+            (
+                "/wiki/Category:Supernovas".to_string(),
+                Ok(r##"
+<div>
+    <ul>
+        <li class="category-page__member">
+            <a href="/wiki/Urouge" class="category-page__member-link" title="Urouge">Urouge</a>
+        </li>
+    </ul>
+</div>"##
+                    .to_string()),
+            ),
+            (
+                "/wiki/Urouge".to_string(),
+                Ok(r##"
+<main>
+    <span class="mw-page-title-main">Urouge</span>
+    <div id="mw-content-text">
+        <aside class="portable-infobox">
+            <figure class="pi-image">
+                <a href="/urouge.png?cb=1" class="image"></a>
+            </figure>
+            <section>
+                <div class="pi-item pi-data" data-source="jname">
+                    <div class="pi-data-value pi-font">ウルージ</div>
+                </div>
+                <div class="pi-item pi-data" data-source="rname">
+                    <div class="pi-data-value pi-font"><i>Urūji</i></div>
+                </div>
+                <div class="pi-item pi-data" data-source="epithet">
+                    <div class="pi-data-value pi-font">"Mad Monk"</div>
+                </div>
+                <div class="pi-item pi-data" data-source="affiliation">
+                    <div class="pi-data-value pi-font"><a href="/wiki/Fallen_Monk_Pirates">Fallen Monk Pirates</a></div>
+                </div>
+                <div class="pi-item pi-data" data-source="occupation">
+                    <div class="pi-data-value pi-font"><a href="/wiki/Pirate">Pirate</a> Captain<br>Monk (former)</div>
+                </div>
+                <div class="pi-item pi-data" data-source="origin">
+                    <div class="pi-data-value pi-font"><a href="/wiki/Sky_Island">Sky Island</a></div>
+                </div>
+                <div class="pi-item pi-data" data-source="status">
+                    <div class="pi-data-value pi-font">Alive</div>
+                </div>
+                <div class="pi-item pi-data" data-source="age">
+                    <div class="pi-data-value pi-font">45 (debut)</div>
+                </div>
+                <div class="pi-item pi-data" data-source="bounty">
+                    <div class="pi-data-value pi-font">108,000,000</div>
+                </div>
+            </section>
+            <section>
+                <div class="pi-item pi-data" data-source="dfname">
+                    <div class="pi-data-value pi-font"><a href="/wiki/Unnamed_Devil_Fruit">Unnamed Devil Fruit</a></div>
+                </div>
+            </section>
+        </aside>
+    </div>
+</main>
+                "##.to_string()),
+            ),
+            (
+                "/wiki/Gol_D._Roger".to_string(),
+                Ok(r##"
+<main>
+    <span class="mw-page-title-main">Gol D. Roger</span>
+    <div id="mw-content-text">
+        <aside class="portable-infobox">
+            <section>
+                <div class="pi-item pi-data" data-source="status">
+                    <div class="pi-data-value pi-font">Deceased</div>
+                </div>
+            </section>
+        </aside>
+    </div>
+</main>
+                "##.to_string()),
+            ),
+        ]);
+        let crawler = Arc::new(CategoryScraper::new(fetcher.clone()));
+        let scraper = CharacterScraper::new(fetcher, crawler);
+        let characters = scraper.scrape().await.unwrap();
+        assert_eq!(characters.len(), 2);
+
+        let roger = &characters[0];
+        assert_eq!(roger.name, "Gol D. Roger");
+        assert_eq!(roger.status, CharacterStatus::Deceased);
+        assert_eq!(roger.categories, vec!["Characters"]);
+
+        let urouge = &characters[1];
+        assert_eq!(urouge.get_path(), "/wiki/Urouge");
+        assert_eq!(urouge.jp_name, "ウルージ");
+        assert_eq!(urouge.romanized_name, "Urūji");
+        assert_eq!(urouge.epithet.as_deref(), Some("Mad Monk"));
+        assert_eq!(urouge.affiliations[0].name, "Fallen Monk Pirates");
+        assert_eq!(urouge.occupations, vec!["Pirate Captain", "Monk"]);
+        assert_eq!(urouge.origin.as_ref().unwrap().name, "Sky Island");
+        assert_eq!(urouge.status, CharacterStatus::Alive);
+        assert_eq!(urouge.age.as_deref(), Some("45 (debut)"));
+        assert_eq!(urouge.bounty, Some(108_000_000));
+        assert_eq!(
+            urouge.devil_fruit.as_ref().unwrap().get_path(),
+            "/wiki/Unnamed_Devil_Fruit"
+        );
+        assert_eq!(urouge.pic_url, "/urouge.png");
+        assert_eq!(urouge.categories, vec!["Supernovas"]);
+    }
+}
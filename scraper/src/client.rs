@@ -1,29 +1,178 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+        RETRY_AFTER,
+    },
+    Client, Proxy, StatusCode,
+};
 
 use crate::types::Error;
 
+/// Response validators used for conditional requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Page {
+    pub body: String,
+    pub validators: Validators,
+}
+
 #[async_trait]
 pub trait FetchHtml: Send + Sync + Debug {
     async fn fetch(&self, url: String) -> Result<String, Error>;
+
+    /// Conditional fetch, `Ok(None)` means the page did not change since `validators`.
+    /// Clients without header support always return the full page.
+    async fn fetch_page(
+        &self,
+        url: String,
+        _validators: Option<Validators>,
+    ) -> Result<Option<Page>, Error> {
+        Ok(Some(Page {
+            body: self.fetch(url).await?,
+            validators: Validators::default(),
+        }))
+    }
+}
+
+/// Settings of the underlying HTTP client.
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    /// replaces the default `opdb-rust/<version>` product token
+    pub user_agent: Option<String>,
+    /// appended to the User-Agent so wiki admins can reach whoever runs the scraper
+    pub contact: Option<String>,
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub proxy: Option<String>,
+    /// extra headers in `Name: value` form
+    pub headers: Vec<String>,
+}
+
+impl ClientConfig {
+    pub fn user_agent(&self) -> String {
+        let product = self
+            .user_agent
+            .clone()
+            .unwrap_or_else(|| concat!("opdb-rust/", env!("CARGO_PKG_VERSION")).to_string());
+        match &self.contact {
+            Some(contact) => format!("{} (+{}; {})", product, env!("CARGO_PKG_HOMEPAGE"), contact),
+            None => format!("{} (+{})", product, env!("CARGO_PKG_HOMEPAGE")),
+        }
+    }
+
+    fn default_headers(&self) -> Result<HeaderMap, Error> {
+        self.headers
+            .iter()
+            .map(|header| {
+                let invalid = || Error::InvalidConfig(format!("invalid header '{}'", header));
+                let (name, value) = header.split_once(':').ok_or_else(invalid)?;
+                let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| invalid())?;
+                let value = HeaderValue::from_str(value.trim()).map_err(|_| invalid())?;
+                Ok((name, value))
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct HttpClientWrapper(pub Client);
 
+impl HttpClientWrapper {
+    pub fn from_config(config: &ClientConfig) -> Result<Self, Error> {
+        let mut builder = Client::builder()
+            .user_agent(config.user_agent())
+            .default_headers(config.default_headers()?);
+        if let Some(timeout) = config.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = config.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &config.proxy {
+            let proxy = Proxy::all(proxy)
+                .map_err(|e| Error::InvalidConfig(format!("invalid proxy '{}': {}", proxy, e)))?;
+            builder = builder.proxy(proxy);
+        }
+        builder
+            .build()
+            .map(Self)
+            .map_err(|e| Error::InvalidConfig(e.to_string()))
+    }
+}
+
 #[async_trait]
 #[cfg_attr(test, mockall::automock)]
 impl FetchHtml for HttpClientWrapper {
     async fn fetch(&self, url: String) -> Result<String, Error> {
-        self.0
-            .get(&url)
-            .send()
+        self.fetch_page(url, None)
             .await
-            .map_err(|r| Error::RequestError(format!("{:?} on url: {}", r, &url)))?
-            .text()
-            .await
-            .map_err(|e| Error::RequestError(format!("{:?} on url: {}", e, &url)))
+            .map(|page| page.unwrap_or_default().body)
+    }
+
+    async fn fetch_page(
+        &self,
+        url: String,
+        validators: Option<Validators>,
+    ) -> Result<Option<Page>, Error> {
+        let mut req = self.0.get(&url);
+        if let Some(v) = validators {
+            if let Some(etag) = v.etag {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = v.last_modified {
+                req = req.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let resp = req.send().await.map_err(|e| {
+            if e.is_timeout() {
+                Error::Timeout(url.clone())
+            } else {
+                Error::Request(format!("{:?} on url: {}", e, &url))
+            }
+        })?;
+
+        let status = resp.status();
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let header = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+        if !status.is_success() {
+            // only the delay-seconds form is supported, HTTP-date values are ignored
+            let retry_after = header(RETRY_AFTER).and_then(|v| v.trim().parse::<u64>().ok());
+            return Err(Error::HttpStatus {
+                url,
+                status: status.as_u16(),
+                retry_after,
+            });
+        }
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+
+        let body = resp.text().await.map_err(|e| {
+            if e.is_timeout() {
+                Error::Timeout(url.clone())
+            } else {
+                Error::Decode {
+                    url: url.clone(),
+                    reason: e.to_string(),
+                }
+            }
+        })?;
+        Ok(Some(Page { body, validators }))
     }
 }
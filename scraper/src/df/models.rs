@@ -1,8 +1,9 @@
+use log::warn;
 use serde::Serialize;
 use std::cmp::Ordering;
 
 use super::types::{DfSubType, DfType, HasDevilFruit};
-use crate::types::NamedJpEn;
+use crate::types::{FirstAppearance, NamedJpEn, NamedUrl};
 
 #[derive(Debug, Serialize, Default)]
 pub struct DfTypeInfo {
@@ -23,6 +24,25 @@ impl DfTypeInfo {
     }
 }
 
+impl DfTypeInfo {
+    /// Logs a warning when `fruits` disagree with the counts published on the wiki.
+    pub fn check_counts(&self, fruits: &[DevilFruit]) {
+        let count = |non_canon: bool| {
+            fruits
+                .iter()
+                .filter(|df| df.df_type == self.df_type && df.non_canon == non_canon)
+                .count()
+        };
+        let (canon, non_canon) = (count(false), count(true));
+        if canon != self.cannon_count as usize || non_canon != self.non_cannon_count as usize {
+            warn!(
+                "{}: scraped {} canon and {} non-canon fruits, the wiki counts {} and {}",
+                self.df_type, canon, non_canon, self.cannon_count, self.non_cannon_count
+            );
+        }
+    }
+}
+
 impl std::fmt::Display for DfTypeInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
@@ -33,32 +53,56 @@ impl std::fmt::Display for DfTypeInfo {
     }
 }
 
+/// Someone who ate a SMILE, with what the fruit gave them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SmileUser {
+    pub user: NamedUrl,
+    pub ability: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DfDetail {
+    pub meaning: String,
+    pub first_appearance: FirstAppearance,
+    pub type_label: String,
+    pub previous_users: Vec<NamedUrl>,
+    pub strengths: Option<String>,
+    pub weaknesses: Option<String>,
+    pub awakening: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DevilFruit {
     pub df_type: DfType,
-    pub df_sub_type: Option<DfSubType>,
+    pub df_sub_types: Vec<DfSubType>,
     pub name: String,
     pub en_name: String,
     pub description: String,
     pub pic_url: String,
     pub df_url: String,
+    pub non_canon: bool,
+    pub users: Vec<NamedUrl>,
+    pub detail: DfDetail,
 }
 
 impl DevilFruit {
     pub fn zoan(
-        sub_type: Option<DfSubType>,
+        sub_types: Vec<DfSubType>,
         name_detail: NamedJpEn,
         pic_url: String,
         df_url: String,
     ) -> Self {
         Self {
             df_type: DfType::Zoan,
-            df_sub_type: sub_type,
+            df_sub_types: sub_types,
             name: name_detail.name,
             en_name: name_detail.en_name,
             description: name_detail.description,
             pic_url,
             df_url,
+            non_canon: false,
+            users: vec![],
+            detail: DfDetail::default(),
         }
     }
 
@@ -70,16 +114,32 @@ impl DevilFruit {
     ) -> Self {
         Self {
             df_type,
-            df_sub_type: None,
+            df_sub_types: vec![],
             name: name_detail.name,
             en_name: name_detail.en_name,
             description: name_detail.description,
             pic_url,
             df_url,
+            non_canon: false,
+            users: vec![],
+            detail: DfDetail::default(),
         }
     }
 }
 
+impl DevilFruit {
+    pub fn with_users(mut self, users: Vec<NamedUrl>) -> Self {
+        self.users = users;
+        self
+    }
+
+    /// marks a fruit only appearing outside of the manga
+    pub fn into_non_canon(mut self) -> Self {
+        self.non_canon = true;
+        self
+    }
+}
+
 impl HasDevilFruit for DevilFruit {
     fn df_type(&self) -> DfType {
         self.df_type
@@ -90,8 +150,8 @@ impl std::fmt::Display for DevilFruit {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "(df_type: {}, df_sub_type: {:?}, name: {}, english name: {}, pic: {}, url: {}, description: {})",
-            self.df_type, self.df_sub_type, self.name, self.en_name, self.pic_url, self.df_url, self.description,
+            "(df_type: {}, df_sub_types: {:?}, name: {}, english name: {}, pic: {}, url: {}, description: {})",
+            self.df_type, self.df_sub_types, self.name, self.en_name, self.pic_url, self.df_url, self.description,
         )
     }
 }
@@ -113,11 +173,14 @@ impl Eq for DevilFruit {}
 impl PartialEq for DevilFruit {
     fn eq(&self, other: &Self) -> bool {
         self.df_type == other.df_type
-            && self.df_sub_type == other.df_sub_type
+            && self.df_sub_types == other.df_sub_types
             && self.name == other.name
             && self.en_name == other.en_name
             && self.description == other.description
             && self.df_url == other.df_url
+            && self.non_canon == other.non_canon
+            && self.users == other.users
+            && self.detail == other.detail
     }
 }
 
@@ -147,38 +210,47 @@ mod tests {
         );
         let df1 = DevilFruit {
             df_type: DfType::Zoan,
-            df_sub_type: Some(DfSubType::MythicalZoan),
+            df_sub_types: vec![DfSubType::Mythical],
             name: "Nika".to_string(),
             en_name: "Nika".to_string(),
             description: "Used to Gomu".to_string(),
             pic_url: "pic".to_string(),
             df_url: "nika".to_string(),
+            non_canon: false,
+            users: vec![],
+            detail: Default::default(),
         };
         let df2 = DevilFruit {
             df_type: DfType::Zoan,
-            df_sub_type: Some(DfSubType::MythicalZoan),
+            df_sub_types: vec![DfSubType::Mythical],
             name: "Zeus".to_string(),
             en_name: "Zeus".to_string(),
             description: "Greek".to_string(),
             pic_url: "pic".to_string(),
             df_url: "zeus".to_string(),
+            non_canon: false,
+            users: vec![],
+            detail: Default::default(),
         };
         let df3 = DevilFruit {
             df_type: DfType::Zoan,
-            df_sub_type: Some(DfSubType::MythicalZoan),
+            df_sub_types: vec![DfSubType::Mythical],
             name: "Nika".to_string(),
             en_name: "Nika".to_string(),
             description: "Used to Gomu".to_string(),
             pic_url: "pic".to_string(),
             df_url: "nika".to_string(),
+            non_canon: false,
+            users: vec![],
+            detail: Default::default(),
         };
         assert_ne!(df1, df2);
         assert_eq!(df1, df3);
         assert!(df1 < df2);
         assert_eq!(
             format!("{}", df1),
-            format!("(df_type: {}, df_sub_type: {:?}, name: {}, english name: {}, pic: {}, url: {}, description: {})",
-                df1.df_type, df1.df_sub_type, df1.name, df1.en_name, df1.pic_url, df1.df_url, df1.description
+            format!("(df_type: {}, df_sub_types: {:?}, name: {}, english name: {}, pic: {}, url: {}, description: {})",
+                df1.df_type, df1.df_sub_types, df1.name, df1.en_name, df1.pic_url, df1.df_url, df1.description
             ));
         assert_eq!(DfType::Zoan, df3.df_type());
     }
@@ -186,7 +258,7 @@ mod tests {
     #[test]
     fn df_constructor_tests() {
         let df = DevilFruit::zoan(
-            Some(DfSubType::MythicalZoan),
+            vec![DfSubType::Mythical],
             NamedJpEn::new(
                 "fruito".to_string(),
                 "fruit".to_string(),
@@ -1,24 +1,27 @@
 use itertools::Itertools as _;
 use lazy_static::lazy_static;
-use log::info;
+use log::{debug, info};
 use regex::Regex;
 use scraper::{ElementRef, Html};
 use std::collections::HashMap;
 use strum::IntoEnumIterator as _;
 
 use crate::{
-    types::{Error, NamedJpEn},
+    types::{Error, NamedJpEn, NamedUrl, UrlTyped},
     utils,
 };
 
 use super::{
-    models::DevilFruit,
+    models::{DevilFruit, DfDetail, SmileUser},
     types::{DfSubType, DfType},
 };
 
+const USER_JOINERS: [&str; 6] = ["and", "or", "then", "later", "formerly", "currently"];
+
 lazy_static! {
     static ref REX_EN_NAME: Regex = Regex::new(r"English version: (.+)").unwrap();
     static ref REX_DESCRIPTION_ZOAN: Regex = Regex::new(r"\) \- (.+)").unwrap();
+    static ref REX_DESCRIPTION: Regex = Regex::new(r"\): (.+)").unwrap();
 }
 
 pub trait DfTypeParser {
@@ -52,7 +55,7 @@ impl DfTypeParser for CanonZoanParser {
                 !(el.value().name() == "h3"
                     && el
                         .first_child()
-                        .and_then(|n| ElementRef::wrap(n))
+                        .and_then(ElementRef::wrap)
                         .and_then(|el| el.value().id())
                         .is_some_and(|s| s != "Canon"))
             })
@@ -67,13 +70,14 @@ impl DfTypeParser for CanonZoanParser {
                 let path = utils::extract_href(el, "a:nth-of-type(1)")?;
 
                 let name_detail = Utils::parse_df_name(el, &REX_EN_NAME, &REX_DESCRIPTION_ZOAN);
-                let sub_type = df_sub_map.get(&path);
+                let sub_types = df_sub_map.get(&path).cloned().unwrap_or_default();
                 // info!("fruit: {:?}", &el.html());
-                let df = DevilFruit::zoan(sub_type.copied(), name_detail, String::new(), path);
+                let df = DevilFruit::zoan(sub_types, name_detail, String::new(), path)
+                    .with_users(Utils::parse_df_users(el));
                 // info!("fruit name: {}", &df);
                 Ok(df)
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<_, Error>>()?;
 
         info!("total Zoan: {}", df_list.len());
 
@@ -121,11 +125,12 @@ macro_rules! impl_canon_paramecia_logia_parser {
                     .map(|el| {
                         let path = utils::extract_href(el, &"a:nth-of-type(1)")?;
                         let name_detail = Utils::parse_df_name(el, &rex_en_name, &rex_desc);
-                        let df = DevilFruit::non_zoan($df_type, name_detail, String::new(), path);
+                        let df = DevilFruit::non_zoan($df_type, name_detail, String::new(), path)
+                            .with_users(Utils::parse_df_users(el));
 
                         Ok(df)
                     })
-                    .collect::<Result<_, _>>()?;
+                    .collect::<Result<_, Error>>()?;
 
                 info!("total {}: {}", $df_type, df_list.len());
 
@@ -138,25 +143,144 @@ macro_rules! impl_canon_paramecia_logia_parser {
 impl_canon_paramecia_logia_parser!(CanonParameciaParser, DfType::Paramecia);
 impl_canon_paramecia_logia_parser!(CanonLogiaParser, DfType::Logia);
 
-pub fn get_parser(df_type: &DfType, canon: bool) -> Box<dyn DfTypeParser> {
+/// Parser of the fruits listed under the h3 "Non-Canon" of a type page.
+pub struct NonCanonParser(pub DfType);
+
+// every ul after h3 "Non-Canon", up to the next h2 or h3
+impl DfTypeParser for NonCanonParser {
+    fn parse(&self, html: &Html) -> Result<Vec<DevilFruit>, Error> {
+        let df_type = self.0;
+        let fruits = Utils::list_section(html, &df_type)?
+            .into_iter()
+            .skip_while(|el| {
+                !(el.value().name() == "h3"
+                    && Utils::heading_id(el).is_some_and(|id| id.starts_with("Non-Canon")))
+            })
+            .skip(1)
+            .take_while(|el| !matches!(el.value().name(), "h2" | "h3"))
+            .filter(|el| el.value().name() == "ul")
+            .flat_map(|el| el.child_elements().collect_vec())
+            .collect_vec();
+        let rex_desc = match df_type {
+            DfType::Zoan => &*REX_DESCRIPTION_ZOAN,
+            _ => &*REX_DESCRIPTION,
+        };
+        let df_list: Vec<_> = fruits
+            .iter()
+            .map(|el| {
+                let path = utils::extract_href(el, "a:nth-of-type(1)")?;
+                let name_detail = Utils::parse_df_name(el, &REX_EN_NAME, rex_desc);
+                let df = match df_type {
+                    DfType::Zoan => DevilFruit::zoan(vec![], name_detail, String::new(), path),
+                    _ => DevilFruit::non_zoan(df_type, name_detail, String::new(), path),
+                };
+                Ok(df.with_users(Utils::parse_df_users(el)).into_non_canon())
+            })
+            .collect::<Result<_, Error>>()?;
+
+        info!("total non-canon {}: {}", df_type, df_list.len());
+
+        Ok(df_list)
+    }
+}
+
+/// list page parser of a fruit type, undetermined fruits have no list page to parse
+pub fn get_parser(df_type: &DfType, canon: bool) -> Result<Box<dyn DfTypeParser>, Error> {
     match (df_type, canon) {
-        (DfType::Zoan, true) => Box::new(CanonZoanParser),
-        (DfType::Logia, true) => Box::new(CanonLogiaParser),
-        (DfType::Paramecia, true) => Box::new(CanonParameciaParser),
-        _ => unimplemented!(),
+        (DfType::Zoan, true) => Ok(Box::new(CanonZoanParser)),
+        (DfType::Logia, true) => Ok(Box::new(CanonLogiaParser)),
+        (DfType::Paramecia, true) => Ok(Box::new(CanonParameciaParser)),
+        (DfType::Zoan | DfType::Logia | DfType::Paramecia, false) => {
+            Ok(Box::new(NonCanonParser(*df_type)))
+        }
+        (DfType::Undetermined, _) => Err(Error::InvalidConfig(format!(
+            "No list parser for {} fruits",
+            df_type
+        ))),
     }
 }
 
+pub fn parse_df_detail(html: &Html) -> DfDetail {
+    let text = |source| {
+        utils::parse_infobox_data(html, source)
+            .and_then(|el| utils::parse_infobox_single_data_text(&el))
+            .map(|t| t.trim().to_string())
+            .unwrap_or_default()
+    };
+    let previous_users = utils::parse_infobox_data(html, "previous")
+        .map(|el| utils::parse_infobox_single_data_named_urls(&el))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|user| !user.get_path().starts_with('#'))
+        .collect();
+    let section = |ids: &[&str]| {
+        ids.iter()
+            .find_map(|id| utils::parse_section_text(html, id))
+    };
+    // the weaknesses are often a subsection of the strengths
+    let strengths = ["Strengths", "Strengths_and_Weaknesses"]
+        .iter()
+        .find_map(|id| utils::parse_section_lead_text(html, id));
+    DfDetail {
+        meaning: text("meaning"),
+        first_appearance: utils::parse_first_appearance(&text("first")),
+        type_label: text("type"),
+        previous_users,
+        strengths,
+        weaknesses: section(&["Weaknesses"]),
+        awakening: section(&["Awakening"]),
+    }
+}
+
+/// sections of the SMILE page listing who ate one, in order of preference
+const SMILE_USER_SECTIONS: [&str; 3] = ["SMILE_Users", "Known_Users", "Users"];
+
+/// Users of the SMILE page, listed either as a list or a table.
+pub fn parse_smile_users(html: &Html) -> Result<Vec<SmileUser>, Error> {
+    let section = SMILE_USER_SECTIONS
+        .iter()
+        .find_map(|id| utils::parse_section_elements(html, id))
+        .ok_or(Error::InvalidStructure(String::from(
+            "missing SMILE users section",
+        )))?;
+    let entry_selector = utils::parse_selector("li, tr")?;
+    let link_selector = utils::parse_selector("a[href]")?;
+    let users = section
+        .iter()
+        .flat_map(|el| el.select(&entry_selector))
+        .filter_map(|entry| {
+            let link = entry
+                .select(&link_selector)
+                .find(|a| a.value().attr("href").is_some_and(|h| !h.starts_with('#')))?;
+            let name = link.text().join("").trim().to_string();
+            let url = link.value().attr("href")?.to_string();
+            let text = entry.text().join(" ");
+            let ability = text
+                .replacen(&name, "", 1)
+                .split_whitespace()
+                .join(" ")
+                .trim_matches(|c: char| c.is_whitespace() || ":-–,".contains(c))
+                .to_string();
+            Some(SmileUser {
+                user: NamedUrl::new(name, url),
+                ability,
+            })
+        })
+        .collect_vec();
+    info!("total SMILE users: {}", users.len());
+    Ok(users)
+}
+
 pub struct Utils;
 
 impl Utils {
     fn parse_df_name(el: &ElementRef, rex_en_name: &Regex, rex_desc: &Regex) -> NamedJpEn {
         let mut en_name = String::new();
         let mut description = String::new();
-        let mut iter = el.text().into_iter();
+        let mut iter = el.text();
         let name = iter.next().unwrap_or_default().to_string();
 
-        while let Some(txt) = iter.next() {
+        for txt in iter.by_ref() {
             if rex_en_name.is_match(txt) {
                 en_name = rex_en_name
                     .captures(txt)
@@ -182,35 +306,96 @@ impl Utils {
         NamedJpEn::new(name, en_name, description)
     }
 
-    fn parse_sub_type(html_doc: &Html) -> Result<HashMap<String, DfSubType>, Error> {
-        let mut sub_type_map = HashMap::new();
+    /// links of the "eaten by" clause of a list entry, up to the end of its sentence or the
+    /// first text that does not join two users, e.g. `during the <a>Dressrosa Arc</a>`
+    fn parse_df_users(el: &ElementRef) -> Vec<NamedUrl> {
+        let mut eaten = false;
+        let mut users = vec![];
+        for node in el.descendants() {
+            if let Some(text) = node.value().as_text() {
+                // link names are not part of the clause wording, e.g. `Marshall D. Teach`
+                let in_link = node
+                    .ancestors()
+                    .filter_map(|n| n.value().as_element())
+                    .any(|e| e.name() == "a");
+                if in_link {
+                    continue;
+                }
+                let text = text.to_lowercase();
+                if text.contains("eaten by") {
+                    eaten = true;
+                } else if eaten && !Self::joins_users(&text) {
+                    break;
+                }
+                continue;
+            }
+            let Some(a) = ElementRef::wrap(node).filter(|e| eaten && e.value().name() == "a")
+            else {
+                continue;
+            };
+            // footnote and anchor links are not users
+            match a.value().attr("href") {
+                Some(href) if !href.starts_with('#') => users.push(NamedUrl::new(
+                    a.text().collect::<String>().trim().to_string(),
+                    href.to_string(),
+                )),
+                _ => {}
+            }
+        }
+        users
+    }
+
+    /// text between two users of the same clause, e.g. `, ` or ` and later `
+    fn joins_users(text: &str) -> bool {
+        !text.contains(['.', ';', ':'])
+            && text
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .all(|w| USER_JOINERS.contains(&w))
+    }
+
+    fn list_section<'a>(html: &'a Html, df_type: &DfType) -> Result<Vec<ElementRef<'a>>, Error> {
+        let siblings = html
+            .select(&utils::parse_selector(&df_type.id_for_fruit_list())?)
+            .next()
+            .and_then(|e| e.parent())
+            .map(|n| n.next_siblings())
+            .ok_or(Error::InvalidStructure(String::from(
+                "invalid sibling node",
+            )))?;
+        Ok(siblings.filter_map(ElementRef::wrap).collect())
+    }
+
+    fn heading_id<'a>(heading: &ElementRef<'a>) -> Option<&'a str> {
+        heading
+            .first_child()
+            .and_then(ElementRef::wrap)
+            .and_then(|el| el.value().id())
+    }
+
+    /// Every sub type section of the Zoan page a fruit is listed in, missing sections are skipped.
+    fn parse_sub_type(html_doc: &Html) -> Result<HashMap<String, Vec<DfSubType>>, Error> {
+        let mut sub_type_map: HashMap<String, Vec<DfSubType>> = HashMap::new();
 
         for df_sub in DfSubType::iter() {
             let sub_type_selector = &utils::parse_selector(&df_sub.id_for_fruit_list())?;
-            let res: Result<(), Error> = html_doc
-                .select(sub_type_selector)
-                .next()
-                .and_then(|e| e.parent())
-                .map(|n| n.next_siblings())
-                .ok_or(Error::InvalidStructure(String::from(
-                    "invalid sibling node",
-                )))?
-                .filter_map(|n| {
-                    if n.value().is_element() {
-                        ElementRef::wrap(n)
-                    } else {
-                        None
-                    }
-                })
+            let Some(heading) = html_doc.select(sub_type_selector).next() else {
+                debug!("no {} section", df_sub);
+                continue;
+            };
+            heading
+                .parent()
+                .into_iter()
+                .flat_map(|n| n.next_siblings())
+                .filter_map(ElementRef::wrap)
                 .filter(|e| e.value().name() == "ul")
                 .take(1)
                 .flat_map(|e| e.child_elements().collect_vec())
                 .try_for_each(|e| {
                     let path = utils::extract_href(&e, "a:nth-of-type(1)")?;
-                    sub_type_map.insert(path, df_sub);
-                    Ok(())
-                });
-            res?;
+                    sub_type_map.entry(path).or_default().push(df_sub);
+                    Ok::<_, Error>(())
+                })?;
         }
 
         Ok(sub_type_map)
@@ -219,13 +404,213 @@ impl Utils {
 
 #[cfg(test)]
 mod tests {
-    use crate::df::types::DfType;
+    use scraper::Html;
+
+    use crate::{
+        df::types::DfType,
+        types::{Error, NamedUrl, UrlTyped},
+    };
 
-    use super::get_parser;
+    use super::{get_parser, parse_df_detail, parse_smile_users, Utils};
 
     #[test]
-    #[should_panic]
     fn test_get_parser() {
-        get_parser(&DfType::Undetermined, true);
+        assert!(get_parser(&DfType::Paramecia, true).is_ok());
+        assert!(matches!(
+            get_parser(&DfType::Undetermined, true),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(get_parser(&DfType::Undetermined, false).is_err());
+    }
+
+    #[test]
+    fn non_canon_parser() {
+        let doc = Html::parse_document(
+            r#"<html><body>
+            <h2><span id="Paramecia-Type_Fruits">Paramecia-Type Fruits</span></h2>
+            <h3><span id="Canon">Canon</span></h3>
+            <dl></dl>
+            <ul>
+                <li><a href="/wiki/Gomu_Gomu_no_Mi" title="Gomu Gomu no Mi">Gomu Gomu no Mi</a> (<i>English version: Gum-Gum Fruit</i>): Rubber body.</li>
+            </ul>
+            <h3><span id="Non-Canon">Non-Canon</span></h3>
+            <h4><span id="Movies">Movies</span></h4>
+            <ul>
+                <li><a href="/wiki/Ato_Ato_no_Mi" title="Ato Ato no Mi">Ato Ato no Mi</a> (<i>English version: Art-Art Fruit</i>): Turns things into art. Eaten by <a href="/wiki/Pickles">Pickles</a>.</li>
+            </ul>
+            <h4><span id="Video_Games">Video Games</span></h4>
+            <ul>
+                <li><a href="/wiki/Kama_Kama_no_Mi" title="Kama Kama no Mi">Kama Kama no Mi</a>: Wind blades.</li>
+            </ul>
+            <h2><span id="Trivia">Trivia</span></h2>
+            <ul><li><a href="/wiki/Trivia">not a fruit</a></li></ul>
+            </body></html>"#,
+        );
+        let canon = get_parser(&DfType::Paramecia, true)
+            .unwrap()
+            .parse(&doc)
+            .unwrap();
+        assert_eq!(canon.len(), 1);
+        assert!(!canon[0].non_canon);
+
+        let non_canon = get_parser(&DfType::Paramecia, false)
+            .unwrap()
+            .parse(&doc)
+            .unwrap();
+        assert_eq!(non_canon.len(), 2);
+        assert!(non_canon.iter().all(|df| df.non_canon));
+        assert_eq!(non_canon[0].df_url, "/wiki/Ato_Ato_no_Mi");
+        assert_eq!(non_canon[0].en_name, "Art-Art Fruit");
+        assert_eq!(
+            non_canon[0].users,
+            vec![NamedUrl::new(
+                "Pickles".to_string(),
+                "/wiki/Pickles".to_string()
+            )]
+        );
+        assert!(non_canon[1].users.is_empty());
+        assert_eq!(non_canon[1].df_type, DfType::Paramecia);
+
+        let missing = Html::parse_document(
+            r#"<h2><span id="Paramecia-Type_Fruits">Paramecia-Type Fruits</span></h2><ul><li><a href="/wiki/Gomu_Gomu_no_Mi">Gomu</a></li></ul>"#,
+        );
+        assert!(get_parser(&DfType::Paramecia, false)
+            .unwrap()
+            .parse(&missing)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn df_users_in_order() {
+        let doc = Html::parse_fragment(
+            r##"<ul><li><a href="/wiki/Gura_Gura_no_Mi">Gura Gura no Mi</a> (<i>English version: Tremor-Tremor Fruit</i>): Creates quakes. Formerly eaten by <a href="/wiki/Edward_Newgate">Edward Newgate</a>, currently eaten by <a href="/wiki/Marshall_D._Teach">Marshall D. Teach</a><sup><a href="#cite_note-1">[1]</a></sup>.</li></ul>"##,
+        );
+        let li = doc
+            .select(&crate::utils::parse_selector("li").unwrap())
+            .next()
+            .unwrap();
+        let users = Utils::parse_df_users(&li);
+        assert_eq!(
+            users,
+            vec![
+                NamedUrl::new(
+                    "Edward Newgate".to_string(),
+                    "/wiki/Edward_Newgate".to_string()
+                ),
+                NamedUrl::new(
+                    "Marshall D. Teach".to_string(),
+                    "/wiki/Marshall_D._Teach".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn df_users_stop_at_end_of_clause() {
+        let doc = Html::parse_fragment(
+            r##"<ul><li><a href="/wiki/Hito_Hito_no_Mi,_Model:_Nika">Hito Hito no Mi, Model: Nika</a>: Eaten by <a href="/wiki/Joy_Boy">Joy Boy</a> and later <a href="/wiki/Monkey_D._Luffy">Monkey D. Luffy</a>, as revealed in <a href="/wiki/Chapter_1044">Chapter 1044</a>. See <a href="/wiki/Gear_5">Gear 5</a>.</li></ul>"##,
+        );
+        let li = doc
+            .select(&crate::utils::parse_selector("li").unwrap())
+            .next()
+            .unwrap();
+        let users = Utils::parse_df_users(&li);
+        assert_eq!(
+            users,
+            vec![
+                NamedUrl::new("Joy Boy".to_string(), "/wiki/Joy_Boy".to_string()),
+                NamedUrl::new(
+                    "Monkey D. Luffy".to_string(),
+                    "/wiki/Monkey_D._Luffy".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn df_detail_page() {
+        let doc = Html::parse_document(
+            r##"<main><div id="mw-content-text">
+            <aside class="portable-infobox">
+                <section>
+                    <div class="pi-item pi-data" data-source="meaning"><h3>Meaning:</h3><div class="pi-data-value">Rubber</div></div>
+                    <div class="pi-item pi-data" data-source="first"><h3>First Appearance:</h3><div class="pi-data-value">Chapter 1; Episode 1</div></div>
+                    <div class="pi-item pi-data" data-source="type"><h3>Type:</h3><div class="pi-data-value"><a href="/wiki/Zoan">Zoan</a> (Mythical)</div></div>
+                    <div class="pi-item pi-data" data-source="previous"><h3>Previous User:</h3><div class="pi-data-value"><a href="/wiki/Joy_Boy">Joy Boy</a><a href="#cite_note-2">[2]</a></div></div>
+                </section>
+            </aside>
+            <h2><span class="mw-headline" id="Strengths_and_Weaknesses">Strengths and Weaknesses</span></h2>
+            <p>The user's body gains the properties of rubber.</p>
+            <h3><span class="mw-headline" id="Weaknesses">Weaknesses</span></h3>
+            <p>Sharp objects.</p>
+            <ul><li>Seastone</li></ul>
+            <div class="mw-heading mw-heading2"><h2 id="Usage">Usage</h2></div>
+            <div class="mw-heading mw-heading3"><h3 id="Awakening">Awakening</h3></div>
+            <p>The user can turn the surroundings into rubber.</p>
+            <div class="mw-heading mw-heading2"><h2 id="Trivia">Trivia</h2></div>
+            <p>Not an ability.</p>
+            </div></main>"##,
+        );
+        let detail = parse_df_detail(&doc);
+        assert_eq!(detail.meaning, "Rubber");
+        assert_eq!(detail.first_appearance.chapter, Some(1));
+        assert_eq!(detail.first_appearance.episode, Some(1));
+        assert_eq!(detail.type_label, "Zoan (Mythical)");
+        assert_eq!(
+            detail.previous_users,
+            vec![NamedUrl::new(
+                "Joy Boy".to_string(),
+                "/wiki/Joy_Boy".to_string()
+            )]
+        );
+        assert_eq!(
+            detail.strengths.as_deref(),
+            Some("The user's body gains the properties of rubber.")
+        );
+        assert_eq!(
+            detail.weaknesses.as_deref(),
+            Some("Sharp objects.\nSeastone")
+        );
+        assert_eq!(
+            detail.awakening.as_deref(),
+            Some("The user can turn the surroundings into rubber.")
+        );
+
+        let empty = parse_df_detail(&Html::parse_document("<main></main>"));
+        assert_eq!(empty.first_appearance.chapter, None);
+        assert!(empty.strengths.is_none());
+    }
+
+    #[test]
+    fn smile_users_from_list_and_table() {
+        let list = Html::parse_document(
+            r##"<h2><span id="Known_Users">Known Users</span></h2>
+            <ul>
+                <li><a href="/wiki/Sheepshead">Sheepshead</a>: Ox</li>
+                <li><a href="/wiki/Holdem">Holdem</a> - Lion</li>
+            </ul>
+            <h2><span id="Trivia">Trivia</span></h2>
+            <ul><li><a href="/wiki/Kaido">Kaido</a> is not a SMILE user</li></ul>"##,
+        );
+        let users = parse_smile_users(&list).unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].user.name, "Sheepshead");
+        assert_eq!(users[0].ability, "Ox");
+        assert_eq!(users[1].ability, "Lion");
+
+        let table = Html::parse_document(
+            r##"<h3><span id="SMILE_Users">SMILE Users</span></h3>
+            <table class="wikitable"><tbody>
+                <tr><th>User</th><th>Animal</th></tr>
+                <tr><td><a href="/wiki/Speed">Speed</a></td><td>Horse</td></tr>
+            </tbody></table>"##,
+        );
+        let users = parse_smile_users(&table).unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].user.get_path(), "/wiki/Speed");
+        assert_eq!(users[0].ability, "Horse");
+
+        assert!(parse_smile_users(&Html::parse_document("<p></p>")).is_err());
     }
 }
@@ -1,37 +1,54 @@
 use itertools::Itertools;
-use log::{error, info};
-use scraper::selectable::Selectable;
+use log::{debug, error, info, warn};
 use scraper::Html;
 use std::collections::HashMap;
 use std::str::FromStr as _;
+use std::sync::Arc;
 use strum::IntoEnumIterator;
+use tokio::task::JoinSet;
 
-use super::models::{DevilFruit, DfTypeInfo};
-use crate::df::parser::get_parser;
+use super::models::{DevilFruit, DfTypeInfo, SmileUser};
+use crate::category::{CrawlOptions, UrlCrawler};
+use crate::df::parser::{get_parser, parse_df_detail, parse_smile_users};
 use crate::df::types::DfType;
 use crate::fetcher::HtmlFetcher;
-use crate::types::{Error, UrlTyped};
+use crate::types::{Error, NamedJpEn, ParseContext, UrlTyped};
 use crate::utils;
 
 pub trait DfScrapable {
     async fn get_dftype_info(&self) -> Result<Vec<DfTypeInfo>, Error>;
     async fn get_df_list(&self) -> Result<Vec<DevilFruit>, Error>;
+    async fn get_smile_users(&self) -> Result<Vec<SmileUser>, Error>;
 }
 
-#[derive(Debug)]
 pub struct DfScraper {
     fetcher: HtmlFetcher,
+    category_crawler: Arc<dyn UrlCrawler>,
+    crawl_options: CrawlOptions,
 }
 
 impl DfScraper {
-    pub fn new(fetcher: HtmlFetcher) -> Self {
-        Self { fetcher }
+    pub fn new(fetcher: HtmlFetcher, category_crawler: Arc<dyn UrlCrawler>) -> Self {
+        Self {
+            fetcher,
+            category_crawler,
+            crawl_options: CrawlOptions::default(),
+        }
+    }
+
+    pub fn with_crawl_options(mut self, options: CrawlOptions) -> Self {
+        self.crawl_options = options;
+        self
     }
 }
 
 impl DfScrapable for DfScraper {
     async fn get_dftype_info(&self) -> Result<Vec<DfTypeInfo>, Error> {
-        let html = self.fetcher.fetch("/wiki/Devil_Fruit").await?;
+        let html = self
+            .fetcher
+            .fetch("/wiki/Devil_Fruit")
+            .await
+            .map(utils::cleanup_html)?;
         let doc = Html::parse_document(&html);
 
         let desc = tokio::try_join!(
@@ -41,31 +58,33 @@ impl DfScrapable for DfScraper {
         );
         let (p_desc, z_desc, l_desc) = desc?;
 
-        let row_selector = utils::parse_selector(
-            "table.wikitable:nth-of-type(1) tr:nth-of-type(n+2):nth-of-type(-n+5)",
-        )?;
-        let td_selector = utils::parse_selector("td")?;
-
-        doc.select(&row_selector)
-            .map(|row| {
-                let cells = row.select(&td_selector).collect_vec();
-                if cells.len() < 3 {
-                    let msg = format!("Expected at least 3 cells, found {}", cells.len());
-                    return Err(Error::InvalidStructure(msg));
-                }
+        let table = utils::Table::find(&doc, &["Canon", "Non-Canon", "Total"])?;
+        let canon = table.column("Canon")?;
+        let non_canon = table.column("Non-Canon")?;
+        let total = table.column("Total")?;
 
-                let dft = cells[0].text().collect_vec()[0].trim();
-                let cc = cells[1].text().collect_vec()[0]
-                    .trim()
-                    .parse::<u32>()
-                    .unwrap();
-                let ncc = cells[2].text().collect_vec()[0]
-                    .trim()
-                    .parse::<u32>()
-                    .unwrap();
-                let df_type = DfType::from_str(dft).map_err(|_| {
-                    Error::InvalidStructure(format!("Unknown DfType '{}': {:?}", dft, row.html()))
-                })?;
+        table
+            .rows()
+            .iter()
+            .filter_map(|row| {
+                let label = row.label();
+                // summary rows such as the totals are not a type
+                let df_type = DfType::from_str(&label)
+                    .inspect_err(|_| debug!("skipping df type table row '{}'", label))
+                    .ok()?;
+                Some((df_type, row))
+            })
+            .map(|(df_type, row)| {
+                let cc: u32 = row.number(canon, "Canon")?;
+                let ncc: u32 = row.number(non_canon, "Non-Canon")?;
+                let sum: u32 = row.number(total, "Total")?;
+                if cc.checked_add(ncc) != Some(sum) {
+                    return Err(Error::TableCell {
+                        row: row.label(),
+                        column: String::from("Total"),
+                        reason: format!("{} is not {} + {}", sum, cc, ncc),
+                    });
+                }
                 let desc = match df_type {
                     DfType::Paramecia => p_desc.trim(),
                     DfType::Zoan => z_desc.trim(),
@@ -80,55 +99,129 @@ impl DfScrapable for DfScraper {
     }
 
     async fn get_df_list(&self) -> Result<Vec<DevilFruit>, Error> {
-        let mut pic_tasks = tokio::task::JoinSet::new();
+        let mut page_tasks = JoinSet::new();
         let mut devil_fruits_map = HashMap::new();
         info!("collecting df...");
-        // Step 1: For each DfType (Paramecia, Zoan, Logia)
-        for df_type in DfType::iter().filter(|t| !t.get_path().is_empty()) {
+        // Step 1: For each DfType (Paramecia, Zoan, Logia), canon and non-canon
+        let df_types = DfType::iter().filter(|t| !t.id_for_fruit_list().is_empty());
+        for (df_type, canon) in df_types.cartesian_product([true, false]) {
             let html = self.fetcher.fetch(&df_type.get_path()).await?;
             let doc = Html::parse_document(&html);
 
-            let df_list = get_parser(&df_type, true).parse(&doc)?;
+            let df_list = get_parser(&df_type, canon)?.parse(&doc)?;
 
-            // Step 2: Store each DevilFruit and prepare to fetch their pictures
+            // Step 2: Store each DevilFruit and prepare to fetch their own pages
             for df in df_list {
                 let df_url = df.df_url.clone();
                 devil_fruits_map.insert(df_url.clone(), df);
 
                 let fetcher = self.fetcher.clone();
-                pic_tasks.spawn(async move {
-                    let html = fetcher.fetch_only(&df_url).await?;
+                page_tasks.spawn(async move {
+                    let html = fetcher.fetch_only(&df_url).await.map(utils::cleanup_html)?;
                     let doc = Html::parse_document(&html);
-                    let pic_url = utils::parse_picture_url(&doc)?;
+                    let pic_url = utils::parse_picture_url(&doc).parse_field(&df_url, "picture")?;
                     let pic = pic_url.first().cloned().unwrap_or_default();
 
-                    Ok::<(String, String), Error>((df_url, pic))
+                    Ok::<_, Error>((df_url, pic, parse_df_detail(&doc)))
                 });
             }
         }
-        // Step 3: Await all picture tasks
-        info!("collecting df pictures...");
-        while let Some(res) = pic_tasks.join_next().await {
+        // Undetermined fruits have no list page, each of them is parsed from its own page
+        // a failed category only costs the undetermined fruits, not the whole list
+        let undetermined = self
+            .category_crawler
+            .crawl(&DfType::Undetermined.get_path(), &self.crawl_options)
+            .await;
+        for e in &undetermined.errors {
+            error!("Error crawling undetermined df {}", e);
+        }
+        let mut undetermined_tasks = JoinSet::new();
+        for df_url in undetermined.membership.into_keys() {
+            if devil_fruits_map.contains_key(&df_url) {
+                continue;
+            }
+            let fetcher = self.fetcher.clone();
+            undetermined_tasks.spawn(parse_undetermined_df(fetcher, df_url));
+        }
+        // Step 3: Await all page tasks
+        info!("collecting df pages...");
+        while let Some(res) = page_tasks.join_next().await {
             match res {
-                Ok(Ok((url, pic_url))) => {
+                Ok(Ok((url, pic_url, detail))) => {
                     if let Some(df) = devil_fruits_map.get_mut(&url) {
                         df.pic_url = pic_url;
+                        df.detail = detail;
                     }
                 }
-                Ok(Err(e)) => error!("Error parsing picture {}", e),
-                Err(e) => error!("Error parsing picture {}", e),
+                Ok(Err(e)) if e.is_not_found() => warn!("Devil fruit page no longer exists: {}", e),
+                Ok(Err(e)) => error!("Error parsing df page {}", e),
+                Err(e) => error!("Error parsing df page {}", e),
+            }
+        }
+
+        info!("collecting undetermined df...");
+        while let Some(res) = undetermined_tasks.join_next().await {
+            match res {
+                Ok(Ok(df)) => {
+                    devil_fruits_map.insert(df.df_url.clone(), df);
+                }
+                Ok(Err(e)) if e.is_not_found() => warn!("Devil fruit page no longer exists: {}", e),
+                Ok(Err(e)) => error!("Error parsing undetermined df {}", e),
+                Err(e) => error!("JoinSet error {}", e),
             }
         }
 
         Ok(devil_fruits_map.into_values().sorted().collect_vec())
     }
+
+    async fn get_smile_users(&self) -> Result<Vec<SmileUser>, Error> {
+        let html = self
+            .fetcher
+            .fetch("/wiki/SMILE")
+            .await
+            .map(utils::cleanup_html)?;
+        let doc = Html::parse_document(&html);
+        parse_smile_users(&doc).parse_field("/wiki/SMILE", "users")
+    }
+}
+
+async fn parse_undetermined_df(fetcher: HtmlFetcher, df_url: String) -> Result<DevilFruit, Error> {
+    let html = fetcher.fetch_only(&df_url).await.map(utils::cleanup_html)?;
+    let doc = Html::parse_document(&html);
+    let pic_url = utils::parse_picture_url(&doc)
+        .parse_field(&df_url, "picture")?
+        .first()
+        .cloned()
+        .unwrap_or_default();
+    let name = utils::parse_main_page_title(&doc).parse_field(&df_url, "title")?;
+    let description =
+        utils::parse_main_page_first_paragraph(&doc).parse_field(&df_url, "description")?;
+    let non_canon = utils::parse_is_non_cannon(&doc).parse_field(&df_url, "categories")?;
+    let en_name = utils::parse_infobox_data(&doc, "ename")
+        .and_then(|el| utils::parse_infobox_single_data_text(&el))
+        .unwrap_or_default();
+    let users = utils::parse_infobox_data(&doc, "user")
+        .map(|el| utils::parse_infobox_single_data_named_urls(&el))
+        .unwrap_or_default();
+    let name_detail = NamedJpEn::new(name, en_name.trim().to_string(), description);
+    let mut df =
+        DevilFruit::non_zoan(DfType::Undetermined, name_detail, pic_url, df_url).with_users(users);
+    df.detail = parse_df_detail(&doc);
+    Ok(if non_canon { df.into_non_canon() } else { df })
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use crate::{
-        df::scraper::{DfScrapable, DfScraper},
+        category::CategoryScraper,
+        df::{
+            scraper::{DfScrapable, DfScraper},
+            types::{DfSubType, DfType},
+        },
         fetcher::mocks::prepare_fetcher,
+        types::Error,
     };
 
     #[tokio::test]
@@ -154,18 +247,70 @@ mod tests {
             </body></html>"#
                 .to_string()),
         )]);
-        let scrape = DfScraper::new(fetcher);
+        let crawler = Arc::new(CategoryScraper::new(fetcher.clone()));
+        let scrape = DfScraper::new(fetcher, crawler);
         let result = scrape.get_dftype_info().await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 4);
     }
 
     #[tokio::test]
-    async fn get_df_list() {
-        let fetcher = prepare_fetcher([
-            (
-                "/wiki/Logia".to_string(),
-                Ok(r#"<html><body>
+    async fn get_type_info_rejects_bad_counts() {
+        let page = |row: &str| {
+            format!(
+                r#"<html><body>
+                <h4><span id="Paramecia">Paramecia</span></h4><p>Paramecia Text</p>
+                <h4><span id="Zoan">Zoan</span></h4><p>Zoan Text</p>
+                <h4><span id="Logia">Logia</span></h4><p>Logia Text</p>
+                <table class="wikitable"><tbody>
+                    <tr><th>Type</th><th>Total</th><th>Non-Canon</th><th>Canon</th></tr>
+                    {}
+                </tbody></table>
+            </body></html>"#,
+                row
+            )
+        };
+        let fetch = |row: &str| {
+            let fetcher = prepare_fetcher([("/wiki/Devil_Fruit".to_string(), Ok(page(row)))]);
+            let crawler = Arc::new(CategoryScraper::new(fetcher.clone()));
+            DfScraper::new(fetcher, crawler)
+        };
+
+        // columns are found by name, whatever their order
+        let info = fetch("<tr><td>Zoan</td><td>62</td><td>7</td><td>55</td></tr>")
+            .get_dftype_info()
+            .await
+            .unwrap();
+        assert_eq!((info[0].cannon_count, info[0].non_cannon_count), (55, 7));
+
+        let result = fetch("<tr><td>Zoan</td><td>63</td><td>7</td><td>55</td></tr>")
+            .get_dftype_info()
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::TableCell { row, column, .. }) if row == "Zoan" && column == "Total"
+        ));
+
+        let result = fetch("<tr><td>Logia</td><td>16</td><td>3</td><td>?</td></tr>")
+            .get_dftype_info()
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::TableCell { row, column, .. }) if row == "Logia" && column == "Canon"
+        ));
+
+        // counts adding up past u32::MAX are a bad total, not a panic
+        let result =
+            fetch("<tr><td>Zoan</td><td>4294967295</td><td>4294967295</td><td>1</td></tr>")
+                .get_dftype_info()
+                .await;
+        assert!(matches!(
+            result,
+            Err(Error::TableCell { row, column, .. }) if row == "Zoan" && column == "Total"
+        ));
+    }
+
+    const LOGIA_PAGE: &str = r#"<html><body>
                 <h4><span id="Logia-Types">Logia</span></h4>
                 <dl></dl>
                 <h3>Some Text</h3>
@@ -173,12 +318,9 @@ mod tests {
                 <ul>
                     <li><a href="/dfpath_logia" title="Some Logia Df">Some Logia Devil Fruit</a> (<i>English versions: Some En Logia Df</i>): Some description. Eaten by <a href="/character">Df User</a>.</li>
                 </ul>
-                </body></html>"#
-                    .to_string()),
-            ),
-            (
-                "/wiki/Paramecia".to_string(),
-                Ok(r#"<html><body>
+                </body></html>"#;
+
+    const PARAMECIA_PAGE: &str = r#"<html><body>
                 <h4><span id="Paramecia-Type_Fruits">Paramecia</span></h4>
                 <dl></dl>
                 <h3>Some Text</h3>
@@ -186,12 +328,9 @@ mod tests {
                 <ul>
                     <li><a href="/dfpath_paramecia" title="Some Paramecia Df">Some Paramecia Devil Fruit</a> (<i>English versions: Some En Paramecia Df</i>): Some description. Eaten by <a href="/character">Df User</a>.</li>
                 </ul>
-                </body></html>"#
-                    .to_string()),
-            ),
-            (
-                "/wiki/Zoan".to_string(),
-                Ok(r#"<html><body>
+                </body></html>"#;
+
+    const ZOAN_PAGE: &str = r#"<html><body>
                 <h4><span id="Ancient_Zoan">Ancient Zoan</span></h4>
                 <dl></dl>
                 <p>wow</p>
@@ -204,6 +343,11 @@ mod tests {
                 <ul>
                     <li><a href="/dfpath-mythical-zoan" title="Some Mythical Zoan">Some mythical zoan</a>: Eaten by <a href="/characterx" title="X">X</a>.</li>
                 </ul>
+                <h4><span id="Artificial_Zoan">Artificial Zoan</span></h4>
+                <p>wow</p>
+                <ul>
+                    <li><a href="/dfpath-ancient-zoan" title="Some Ancient Zoan">Some ancient zoan</a>: Eaten by <a href="/characterx" title="X">X</a>.</li>
+                </ul>
                 <h4><span id="List_of_Zoan-Type_Fruits">Zoan</span></h4>
                 <h3>Some Text</h3>
                 <dl></dl>
@@ -211,7 +355,39 @@ mod tests {
                     <li><a href="/dfpath-zoan" title="Some Zoan Df">Some Zoan Devil Fruit</a> (<i>English versions: Some En Zoan Df</i>): Some description. Eaten by <a href="/character">Df User</a>.</li>
                     <li><a href="/dfpath-ancient-zoan" title="Some Ancient Zoan">Some ancient zoan</a> (<i>English versions: Some En Ancient Zoan Df</i>): Some description. Eaten by <a href="/characterx">X</a>.</li>
                 </ul>
-                </body></html>"#
+                </body></html>"#;
+
+    #[tokio::test]
+    async fn get_df_list() {
+        let fetcher = prepare_fetcher([
+            ("/wiki/Logia".to_string(), Ok(LOGIA_PAGE.to_string())),
+            ("/wiki/Paramecia".to_string(), Ok(PARAMECIA_PAGE.to_string())),
+            ("/wiki/Zoan".to_string(), Ok(ZOAN_PAGE.to_string())),
+            (
+                "/wiki/Category:Undetermined_Devil_Fruits".to_string(),
+                Ok(r#"<ul>
+                    <li class="category-page__member"><a href="/wiki/Kage_Kage_no_Mi_(Sanjuan)" class="category-page__member-link">Sanjuan's fruit</a></li>
+                    <li class="category-page__member"><a href="/dfpath-zoan" class="category-page__member-link">Some Zoan Devil Fruit</a></li>
+                </ul>"#
+                    .to_string()),
+            ),
+            (
+                "/wiki/Kage_Kage_no_Mi_(Sanjuan)".to_string(),
+                Ok(r#"<main>
+                <span class="mw-page-title-main">Unnamed Devil Fruit</span>
+                <div class="page-header__categories"><a href="/wiki/Category:Undetermined_Devil_Fruits">Undetermined Devil Fruits</a></div>
+                <div id="mw-content-text">
+                    <p></p>
+                    <aside class="portable-infobox">
+                        <figure class="pi-image"><a href="/undetermined-pic" class="image"></a></figure>
+                        <section>
+                            <div class="pi-item pi-data" data-source="ename"><h3>English Name:</h3><div class="pi-data-value"> Unknown </div></div>
+                        </section>
+                    </aside>
+                    <p></p>
+                    <p>A fruit whose type was never revealed.</p>
+                </div>
+                </main>"#
                     .to_string()),
             ),
             (
@@ -222,8 +398,55 @@ mod tests {
                     .to_string()),
             ),
         ]);
-        let scrape = DfScraper::new(fetcher);
+        let crawler = Arc::new(CategoryScraper::new(fetcher.clone()));
+        let scrape = DfScraper::new(fetcher, crawler);
+        let df_list = scrape.get_df_list().await.unwrap();
+        assert_eq!(df_list.len(), 5);
+        let undetermined = df_list
+            .iter()
+            .find(|df| df.df_type == DfType::Undetermined)
+            .unwrap();
+        assert_eq!(undetermined.df_url, "/wiki/Kage_Kage_no_Mi_(Sanjuan)");
+        assert_eq!(undetermined.name, "Unnamed Devil Fruit");
+        assert_eq!(undetermined.en_name, "Unknown");
+        assert_eq!(undetermined.pic_url, "/undetermined-pic");
+        assert_eq!(
+            undetermined.description,
+            "A fruit whose type was never revealed."
+        );
+        assert!(!undetermined.non_canon);
+        let ancient = df_list
+            .iter()
+            .find(|df| df.df_url == "/dfpath-ancient-zoan")
+            .unwrap();
+        assert_eq!(
+            ancient.df_sub_types,
+            vec![DfSubType::Ancient, DfSubType::Artificial]
+        );
+    }
+
+    #[tokio::test]
+    async fn get_df_list_without_undetermined_category() {
+        let fetcher = prepare_fetcher([
+            ("/wiki/Logia".to_string(), Ok(LOGIA_PAGE.to_string())),
+            (
+                "/wiki/Paramecia".to_string(),
+                Ok(PARAMECIA_PAGE.to_string()),
+            ),
+            ("/wiki/Zoan".to_string(), Ok(ZOAN_PAGE.to_string())),
+            (
+                "/wiki/Category:Undetermined_Devil_Fruits".to_string(),
+                Err(Error::HttpStatus {
+                    url: "/wiki/Category:Undetermined_Devil_Fruits".to_string(),
+                    status: 503,
+                    retry_after: None,
+                }),
+            ),
+        ]);
+        let crawler = Arc::new(CategoryScraper::new(fetcher.clone()));
+        let scrape = DfScraper::new(fetcher, crawler);
         let df_list = scrape.get_df_list().await.unwrap();
         assert_eq!(df_list.len(), 4);
+        assert!(df_list.iter().all(|df| df.df_type != DfType::Undetermined));
     }
 }
@@ -30,7 +30,8 @@ impl UrlTyped for DfType {
             DfType::Logia => "/wiki/Logia".to_string(),
             DfType::Zoan => "/wiki/Zoan".to_string(),
             DfType::Paramecia => "/wiki/Paramecia".to_string(),
-            DfType::Undetermined => String::new(),
+            // no list page, the fruits are only gathered in a category
+            DfType::Undetermined => "/wiki/Category:Undetermined_Devil_Fruits".to_string(),
         }
     }
 }
@@ -50,14 +51,28 @@ impl DfType {
     Debug, Clone, Copy, EnumString, PartialEq, Display, EnumIter, Serialize, Eq, PartialOrd, Ord,
 )]
 pub enum DfSubType {
-    AncientZoan,
-    MythicalZoan,
+    #[strum(serialize = "AncientZoan")]
+    #[serde(rename = "AncientZoan")]
+    Ancient,
+    #[strum(serialize = "MythicalZoan")]
+    #[serde(rename = "MythicalZoan")]
+    Mythical,
+    /// man-made fruits, SMILEs included
+    #[strum(serialize = "ArtificialZoan")]
+    #[serde(rename = "ArtificialZoan")]
+    Artificial,
+    #[strum(serialize = "CarnivorousZoan")]
+    #[serde(rename = "CarnivorousZoan")]
+    Carnivorous,
 }
 
 impl UrlTyped for DfSubType {
     fn get_path(&self) -> String {
         match self {
-            DfSubType::AncientZoan | DfSubType::MythicalZoan => "/wiki/Zoan".to_string(),
+            DfSubType::Ancient
+            | DfSubType::Mythical
+            | DfSubType::Artificial
+            | DfSubType::Carnivorous => "/wiki/Zoan".to_string(),
         }
     }
 }
@@ -65,12 +80,15 @@ impl UrlTyped for DfSubType {
 impl DfSubType {
     pub fn id_for_fruit_list(&self) -> String {
         match self {
-            DfSubType::AncientZoan => "#Ancient_Zoan".to_string(),
-            DfSubType::MythicalZoan => "#Mythical_Zoan".to_string(),
+            DfSubType::Ancient => "#Ancient_Zoan".to_string(),
+            DfSubType::Mythical => "#Mythical_Zoan".to_string(),
+            DfSubType::Artificial => "#Artificial_Zoan".to_string(),
+            DfSubType::Carnivorous => "#Carnivorous_Zoan".to_string(),
         }
     }
 }
 
+#[allow(dead_code)]
 pub trait HasDevilFruit {
     fn df_type(&self) -> DfType;
 }
@@ -93,14 +111,19 @@ mod tests {
         assert_eq!(dft.get_path(), "/wiki/Paramecia");
         assert_eq!(dft.id_for_fruit_list(), "#Paramecia-Type_Fruits");
         let dft = DfType::Undetermined;
-        assert_eq!(dft.get_path(), "");
+        assert_eq!(dft.get_path(), "/wiki/Category:Undetermined_Devil_Fruits");
         assert_eq!(dft.id_for_fruit_list(), "");
 
-        let dfsub = DfSubType::AncientZoan;
+        let dfsub = DfSubType::Ancient;
         assert_eq!(dfsub.get_path(), "/wiki/Zoan");
         assert_eq!(dfsub.id_for_fruit_list(), "#Ancient_Zoan");
-        let dfsub = DfSubType::MythicalZoan;
+        let dfsub = DfSubType::Mythical;
         assert_eq!(dfsub.get_path(), "/wiki/Zoan");
         assert_eq!(dfsub.id_for_fruit_list(), "#Mythical_Zoan");
+        assert_eq!(dfsub.to_string(), "MythicalZoan");
+        assert_eq!(
+            serde_json::to_string(&DfSubType::Artificial).unwrap(),
+            r#""ArtificialZoan""#
+        );
     }
 }
@@ -1,47 +1,93 @@
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::Mutex;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::OnceCell;
 
-use crate::{client::FetchHtml, types::Error};
+use crate::{
+    cache::{CacheEntry, DiskCache},
+    client::FetchHtml,
+    types::Error,
+};
 
-pub type ArcMapHtml = Arc<Mutex<HashMap<String, String>>>;
+/// One cell per url path, concurrent fetches of the same path wait on the same cell
+pub type ArcMapHtml = Arc<Mutex<HashMap<String, Arc<OnceCell<String>>>>>;
 
 #[derive(Debug, Clone)]
 pub struct HtmlFetcher {
     base_url: String,
     client: Arc<dyn FetchHtml>,
     cache: ArcMapHtml,
+    disk_cache: Option<Arc<DiskCache>>,
 }
 
 impl HtmlFetcher {
-    pub fn new(
-        client: impl FetchHtml + Send + Sync + std::fmt::Debug + 'static,
-        base_url: &str,
-    ) -> Self {
+    pub fn new(client: impl FetchHtml + 'static, base_url: &str) -> Self {
         Self {
             base_url: base_url.to_string(),
             client: Arc::new(client),
             cache: Arc::new(Mutex::new(HashMap::new())),
+            disk_cache: None,
         }
     }
 
+    /// Persist every fetched page, so later runs can skip the network.
+    pub fn with_disk_cache(mut self, disk_cache: DiskCache) -> Self {
+        self.disk_cache = Some(Arc::new(disk_cache));
+        self
+    }
+
     pub async fn fetch(&self, url_path: &str) -> Result<String, Error> {
-        let mut cache = self.cache.lock().await;
-        if let Some(html) = cache.get(url_path) {
-            return Ok(html.clone());
-        }
+        // the lock only guards the map, never the network request
+        let cell = self
+            .cache
+            .lock()
+            .unwrap()
+            .entry(url_path.to_string())
+            .or_default()
+            .clone();
+        // a failed fetch leaves the cell empty, so the next caller tries again
+        cell.get_or_try_init(|| self.fetch_only(url_path))
+            .await
+            .cloned()
+    }
 
-        let html = self
-            .client
-            .fetch(format!("{}{}", &self.base_url, &url_path))
-            .await?;
-        cache.insert(url_path.to_string(), html.clone());
-        Ok(html)
+    #[cfg(test)]
+    fn cached(&self, url_path: &str) -> Option<String> {
+        self.cache
+            .lock()
+            .unwrap()
+            .get(url_path)
+            .and_then(|cell| cell.get().cloned())
     }
 
     pub async fn fetch_only(&self, url_path: &str) -> Result<String, Error> {
-        self.client
-            .fetch(format!("{}{}", &self.base_url, url_path))
-            .await
+        let url = format!("{}{}", &self.base_url, url_path);
+        let Some(disk_cache) = &self.disk_cache else {
+            return self.client.fetch(url).await;
+        };
+
+        let cached = disk_cache.get(url_path).await;
+        if let Some(entry) = cached.as_ref().filter(|e| disk_cache.is_fresh(e)) {
+            return Ok(entry.body.clone());
+        }
+        // stale entries are revalidated with their ETag / Last-Modified
+        let validators = cached.as_ref().map(CacheEntry::validators);
+        let entry = match (self.client.fetch_page(url, validators).await?, cached) {
+            (Some(page), _) => CacheEntry::new(url_path, page),
+            (None, Some(mut entry)) => {
+                entry.touch();
+                entry
+            }
+            (None, None) => {
+                return Err(Error::Request(format!(
+                    "Not modified response without cached page on url: {}",
+                    url_path
+                )))
+            }
+        };
+        disk_cache.store(&entry).await;
+        Ok(entry.body)
     }
 }
 
@@ -66,28 +112,45 @@ pub mod mocks {
             self.res_req
                 .get(&url)
                 .cloned()
-                .ok_or(Error::RequestError(url))
+                .ok_or(Error::Request(url))
                 .and_then(|r| r)
         }
     }
 
+    pub fn prepare_client<const N: usize>(arr: [(String, Result<String, Error>); N]) -> MockClient {
+        MockClient {
+            res_req: HashMap::from(arr),
+        }
+    }
+
     pub fn prepare_fetcher<const N: usize>(
         arr: [(String, Result<String, Error>); N],
     ) -> HtmlFetcher {
-        let client = MockClient {
-            res_req: HashMap::from(arr),
-        };
-
-        HtmlFetcher::new(client, "")
+        HtmlFetcher::new(prepare_client(arr), "")
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
     use mockito::Server;
+    use tokio::task::JoinSet;
 
     use super::HtmlFetcher;
-    use crate::{client::HttpClientWrapper, fetcher::mocks::prepare_fetcher};
+    use crate::{
+        cache::{mocks::temp_dir, DiskCache},
+        client::{ClientConfig, FetchHtml, HttpClientWrapper},
+        fetcher::mocks::prepare_fetcher,
+        types::Error,
+    };
 
     #[tokio::test]
     async fn valid_fetcher() {
@@ -96,17 +159,48 @@ mod tests {
         let resp = fetcher.fetch_only("url").await;
         assert!(resp.is_ok());
         assert_eq!(resp.unwrap(), "htmls");
-        assert!(!fetcher.cache.lock().await.contains_key("url"));
+        assert!(fetcher.cached("url").is_none());
 
         let resp = fetcher.fetch("url").await;
         assert!(resp.is_ok());
         assert_eq!(resp.unwrap(), "htmls");
-        assert!(fetcher.cache.lock().await.contains_key("url"));
+        assert_eq!(fetcher.cached("url").as_deref(), Some("htmls"));
 
         let resp = fetcher.fetch("url").await;
         assert_eq!(resp.unwrap(), "htmls");
     }
 
+    #[derive(Debug, Default)]
+    struct SlowClient {
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl FetchHtml for SlowClient {
+        async fn fetch(&self, url: String) -> Result<String, Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(url)
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_fetches_share_one_request() {
+        let client = SlowClient::default();
+        let calls = client.calls.clone();
+        let fetcher = HtmlFetcher::new(client, "");
+
+        let mut tasks = JoinSet::new();
+        for i in 0..10 {
+            let fetcher = fetcher.clone();
+            let path = if i % 2 == 0 { "/even" } else { "/odd" };
+            tasks.spawn(async move { fetcher.fetch(path).await });
+        }
+        let pages = tasks.join_all().await;
+        assert!(pages.iter().all(|p| p.is_ok()));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
     #[tokio::test]
     async fn fetcher_with_mock_server() {
         let mut server = Server::new_async().await;
@@ -123,4 +217,108 @@ mod tests {
         mocked.assert_async().await;
         assert_eq!(resp.unwrap(), "Will of D");
     }
+
+    #[tokio::test]
+    async fn fetcher_with_configured_client() {
+        let mut server = Server::new_async().await;
+        let mocked = server
+            .mock("GET", "/a_path")
+            .match_header(
+                "user-agent",
+                concat!(
+                    "opdb-rust/",
+                    env!("CARGO_PKG_VERSION"),
+                    " (+",
+                    env!("CARGO_PKG_HOMEPAGE"),
+                    "; luffy@example.com)"
+                ),
+            )
+            .match_header("x-crew", "Straw Hat")
+            .with_body("Will of D")
+            .create_async()
+            .await;
+
+        let config = ClientConfig {
+            contact: Some("luffy@example.com".to_string()),
+            timeout: Some(Duration::from_secs(5)),
+            headers: vec!["X-Crew: Straw Hat".to_string()],
+            ..Default::default()
+        };
+        let client = HttpClientWrapper::from_config(&config).unwrap();
+        let fetcher = HtmlFetcher::new(client, &server.url());
+
+        let resp = fetcher.fetch("/a_path").await;
+        mocked.assert_async().await;
+        assert_eq!(resp.unwrap(), "Will of D");
+
+        let invalid = ClientConfig {
+            headers: vec!["no separator".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            HttpClientWrapper::from_config(&invalid),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fetcher_with_error_status() {
+        let mut server = Server::new_async().await;
+        let mocked = server
+            .mock("GET", "/missing")
+            .with_status(404)
+            .with_body("<html>There is currently no text in this page.</html>")
+            .create_async()
+            .await;
+
+        let client = HttpClientWrapper(reqwest::Client::builder().build().unwrap());
+        let fetcher = HtmlFetcher::new(client, &server.url());
+
+        let resp = fetcher.fetch("/missing").await;
+        mocked.assert_async().await;
+        let err = resp.unwrap_err();
+        assert!(matches!(err, Error::HttpStatus { status: 404, .. }));
+        assert!(err.is_not_found());
+        assert!(fetcher.cached("/missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn fetcher_with_disk_cache() {
+        let dir = temp_dir("fetcher-disk-cache");
+        let mut server = Server::new_async().await;
+        let first = server
+            .mock("GET", "/a_path")
+            .with_header("etag", "\"v1\"")
+            .with_body("Will of D")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = HttpClientWrapper(reqwest::Client::builder().build().unwrap());
+        let fetcher = HtmlFetcher::new(client.clone(), &server.url())
+            .with_disk_cache(DiskCache::new(&dir, Duration::from_secs(60)));
+        assert_eq!(fetcher.fetch_only("/a_path").await.unwrap(), "Will of D");
+        first.assert_async().await;
+
+        // a new process within the ttl does not touch the network at all
+        let fetcher = HtmlFetcher::new(client.clone(), &server.url())
+            .with_disk_cache(DiskCache::new(&dir, Duration::from_secs(60)));
+        assert_eq!(fetcher.fetch("/a_path").await.unwrap(), "Will of D");
+        first.assert_async().await;
+
+        // once expired, the page is revalidated with its etag
+        let revalidate = server
+            .mock("GET", "/a_path")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .expect(1)
+            .create_async()
+            .await;
+        let fetcher = HtmlFetcher::new(client, &server.url())
+            .with_disk_cache(DiskCache::new(&dir, Duration::ZERO));
+        assert_eq!(fetcher.fetch_only("/a_path").await.unwrap(), "Will of D");
+        revalidate.assert_async().await;
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+use crate::types::{FirstAppearance, NamedUrl, Sea, UrlTyped};
+
+#[derive(Debug, Default, Serialize)]
+pub struct Location {
+    pub name: String,
+    pub jp_name: String,
+    pub romanized_name: String,
+    pub region: Option<NamedUrl>,
+    pub seas: Vec<Sea>,
+    pub affiliations: Vec<NamedUrl>,
+    pub population: Option<String>,
+    pub first_appearance: FirstAppearance,
+    pub pic_url: String,
+    pub non_cannon: bool,
+    pub categories: Vec<String>,
+    url: String,
+}
+
+impl Location {
+    pub fn new(name: String, url: String, pic_url: String, non_cannon: bool) -> Self {
+        Self {
+            name,
+            url,
+            pic_url,
+            non_cannon,
+            ..Default::default()
+        }
+    }
+
+    /// `region` also names seas, set it first.
+    pub fn with_categories(mut self, categories: Vec<String>) -> Self {
+        let names = categories
+            .iter()
+            .cloned()
+            .chain(self.region.iter().map(|r| r.name.clone()))
+            .collect::<Vec<_>>();
+        self.seas = Sea::from_categories(&names);
+        self.categories = categories;
+        self
+    }
+}
+
+impl UrlTyped for Location {
+    fn get_path(&self) -> String {
+        self.url.clone()
+    }
+}
+
+impl Eq for Location {}
+
+impl PartialEq for Location {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url
+    }
+}
+
+impl Ord for Location {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.url.cmp(&other.url)
+    }
+}
+
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
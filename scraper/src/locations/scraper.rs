@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use scraper::Html;
+
+use crate::{
+    category::{self, CrawlOptions, UrlCrawler},
+    fetcher::HtmlFetcher,
+    types::{Error, ParseContext},
+    utils,
+};
+
+use super::models::Location;
+
+pub struct LocationScraper {
+    fetcher: HtmlFetcher,
+    category_crawler: Arc<dyn UrlCrawler>,
+    crawl_options: CrawlOptions,
+}
+
+impl LocationScraper {
+    pub fn new(fetcher: HtmlFetcher, category_crawler: Arc<dyn UrlCrawler>) -> Self {
+        Self {
+            fetcher,
+            category_crawler,
+            crawl_options: CrawlOptions::default(),
+        }
+    }
+
+    pub fn with_crawl_options(mut self, options: CrawlOptions) -> Self {
+        self.crawl_options = options;
+        self
+    }
+
+    pub async fn scrape(&self) -> Result<Vec<Location>, Error> {
+        let fetcher = self.fetcher.clone();
+        category::parse_members(
+            self.category_crawler.as_ref(),
+            "/wiki/Category:Locations",
+            &self.crawl_options,
+            "location",
+            move |url, categories| parse_location_detail(fetcher.clone(), url, categories),
+        )
+        .await
+    }
+}
+
+async fn parse_location_detail(
+    fetcher: HtmlFetcher,
+    location_url: String,
+    categories: Vec<String>,
+) -> Result<Location, Error> {
+    let html = fetcher
+        .fetch(&location_url)
+        .await
+        .map(utils::cleanup_html)?;
+    let doc = Html::parse_document(&html);
+    let pic_url = utils::parse_picture_url(&doc)
+        .parse_field(&location_url, "picture")?
+        .first()
+        .cloned()
+        .unwrap_or_default();
+    let name = utils::parse_main_page_title(&doc).parse_field(&location_url, "title")?;
+    let non_cannon = utils::parse_is_non_cannon(&doc).parse_field(&location_url, "categories")?;
+    let mut location = Location::new(name, location_url, pic_url, non_cannon);
+    let stat_selector = utils::parse_selector("aside.portable-infobox .pi-data")?;
+    for el in doc.select(&stat_selector) {
+        if let Some(kind) = el.attr("data-source") {
+            match kind {
+                "jname" => location.jp_name = utils::parse_infobox_text(&el).unwrap_or_default(),
+                "rname" => {
+                    location.romanized_name = utils::parse_infobox_text(&el).unwrap_or_default()
+                }
+                "region" => location.region = utils::parse_infobox_links(&el).into_iter().next(),
+                "affiliation" => location
+                    .affiliations
+                    .extend(utils::parse_infobox_links(&el)),
+                "population" => location.population = utils::parse_infobox_text(&el),
+                "first" => {
+                    location.first_appearance = utils::parse_first_appearance(
+                        &utils::parse_infobox_text(&el).unwrap_or_default(),
+                    )
+                }
+                _ => {}
+            }
+        }
+    }
+    // the seas are derived from the region as well, so it has to be parsed first
+    Ok(location.with_categories(categories))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        category::CategoryScraper,
+        fetcher::mocks::prepare_fetcher,
+        locations::scraper::LocationScraper,
+        types::{Sea, UrlTyped},
+    };
+
+    #[tokio::test]
+    async fn test_get() {
+        let fetcher = prepare_fetcher([
+            (
+                "/wiki/Category:Locations".to_string(),
+                Ok(r##"
+<div>
+    <ul>
+        <li class="category-page__member">
+            <a href="/wiki/Category:East_Blue_Locations" class="category-page__member-link" title="Category:East Blue Locations">Category:East Blue Locations</a>
+        </li>
+        <li class="category-page__member">
+            <a href="/wiki/Wano_Country" class="category-page__member-link" title="Wano Country">Wano Country</a>
+        </li>
+    </ul>
+</div>"##
+                    .to_string()),
+            ),
+            (
+                "/wiki/Category:East_Blue_Locations".to_string(),
+                Ok(r##"
+<div>
+    <ul>
+        <li class="category-page__member">
+            <a href="/wiki/Foosha_Village" class="category-page__member-link" title="Foosha Village">Foosha Village</a>
+        </li>
+    </ul>
+</div>"##
+                    .to_string()),
+            ),
+            (
+                "/wiki/Foosha_Village".to_string(),
+                Ok(r##"
+<main>
+    <span class="mw-page-title-main">Foosha Village</span>
+    <div id="mw-content-text">
+        <aside class="portable-infobox">
+            <figure class="pi-image">
+                <a href="/foosha.png?cb=1" class="image"></a>
+            </figure>
+            <section>
+                <div class="pi-item pi-data" data-source="jname">
+                    <div class="pi-data-value pi-font">フーシャ村</div>
+                </div>
+                <div class="pi-item pi-data" data-source="rname">
+                    <div class="pi-data-value pi-font"><i>Fūsha Mura</i></div>
+                </div>
+                <div class="pi-item pi-data" data-source="region">
+                    <div class="pi-data-value pi-font"><a href="/wiki/East_Blue">East Blue</a></div>
+                </div>
+                <div class="pi-item pi-data" data-source="affiliation">
+                    <div class="pi-data-value pi-font"><a href="/wiki/Goa_Kingdom">Goa Kingdom</a><br><a href="/wiki/World_Government">World Government</a></div>
+                </div>
+                <div class="pi-item pi-data" data-source="first">
+                    <div class="pi-data-value pi-font">Chapter 1; Episode 4</div>
+                </div>
+            </section>
+        </aside>
+    </div>
+</main>
+                "##.to_string()),
+            ),
+            (
+                "/wiki/Wano_Country".to_string(),
+                Ok(r##"
+<main>
+    <span class="mw-page-title-main">Wano Country</span>
+    <div id="mw-content-text">
+        <aside class="portable-infobox">
+            <section>
+                <div class="pi-item pi-data" data-source="region">
+                    <div class="pi-data-value pi-font"><a href="/wiki/New_World">New World</a></div>
+                </div>
+                <div class="pi-item pi-data" data-source="population">
+                    <div class="pi-data-value pi-font">Approximately 3,000,000</div>
+                </div>
+            </section>
+        </aside>
+    </div>
+</main>
+                "##.to_string()),
+            ),
+        ]);
+        let crawler = Arc::new(CategoryScraper::new(fetcher.clone()));
+        let scraper = LocationScraper::new(fetcher, crawler);
+        let locations = scraper.scrape().await.unwrap();
+        assert_eq!(locations.len(), 2);
+
+        let foosha = &locations[0];
+        assert_eq!(foosha.get_path(), "/wiki/Foosha_Village");
+        assert_eq!(foosha.romanized_name, "Fūsha Mura");
+        assert_eq!(foosha.region.as_ref().unwrap().name, "East Blue");
+        assert_eq!(foosha.seas, vec![Sea::EastBlue]);
+        assert_eq!(foosha.affiliations.len(), 2);
+        assert_eq!(foosha.first_appearance.chapter, Some(1));
+        assert_eq!(foosha.pic_url, "/foosha.png");
+
+        let wano = &locations[1];
+        assert_eq!(wano.seas, vec![Sea::NewWorld]);
+        assert_eq!(wano.population.as_deref(), Some("Approximately 3,000,000"));
+        assert_eq!(wano.categories, vec!["Locations"]);
+    }
+}
@@ -1,23 +1,45 @@
+mod cache;
 mod category;
+mod characters;
 mod client;
 mod df;
 mod fetcher;
+mod locations;
+mod mediawiki;
 mod output_writer;
 mod pirates;
+mod ratelimit;
+mod replay;
+mod retry;
 mod ships;
 mod types;
 mod utils;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use category::CategoryScraper;
-use clap::Parser;
-use client::HttpClientWrapper;
+use cache::DiskCache;
+use category::{CategoryScraper, CrawlOptions, UrlCrawler};
+use clap::{Parser, ValueEnum};
+use client::{ClientConfig, HttpClientWrapper};
 use df::scraper::{DfScrapable, DfScraper};
 use fetcher::HtmlFetcher;
-use log::{debug, info};
+use log::{debug, error, info};
+use mediawiki::MediaWikiApi;
 use output_writer::OutputWriter;
 use pirates::scraper::PirateScraper;
+use ratelimit::{RateLimit, RateLimitedClient, RateLimiter};
+use regex::Regex;
+use replay::{RecordingClient, ReplayClient};
+use retry::{RetryClient, RetryPolicy};
+use types::Error;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Source {
+    /// Rendered wiki pages
+    Html,
+    /// MediaWiki api.php endpoints
+    Api,
+}
 
 /// OPDB Scrapper program
 #[derive(Parser)]
@@ -27,6 +49,120 @@ struct MainArgs {
     #[arg(short, long, default_value = "data")]
     output_dir: String,
     category: Option<String>,
+    /// Where category listings, and ship pages, are read from
+    #[arg(long, value_enum, default_value_t = Source::Html)]
+    source: Source,
+    /// Deepest subcategory level crawled below a root category
+    #[arg(long)]
+    category_max_depth: Option<usize>,
+    /// Only crawl subcategories whose title matches this regex
+    #[arg(long)]
+    category_include: Option<Regex>,
+    /// Skip subcategories whose title matches this regex
+    #[arg(long)]
+    category_exclude: Option<Regex>,
+    /// Stop crawling a category tree after collecting this many pages
+    #[arg(long)]
+    category_max_urls: Option<usize>,
+    /// Keep fetched pages in this directory and reuse them on later runs
+    #[arg(long)]
+    cache_dir: Option<String>,
+    /// Seconds before a page in the cache directory is revalidated
+    #[arg(long, default_value_t = 86400)]
+    cache_ttl_secs: u64,
+    /// Record every response into this directory, to be replayed later
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<String>,
+    /// Serve responses recorded with --record from this directory, without network access
+    #[arg(long)]
+    replay: Option<String>,
+    /// Maximum requests per second over the whole run, 0 disables the limit
+    #[arg(long, default_value_t = 10.0)]
+    requests_per_second: f64,
+    /// Maximum requests in flight at once
+    #[arg(long, default_value_t = 20)]
+    max_concurrency: usize,
+    /// Minimum delay in milliseconds between two requests to the same host
+    #[arg(long, default_value_t = 0)]
+    host_delay_ms: u64,
+    /// Replace the product part of the User-Agent header
+    #[arg(long)]
+    user_agent: Option<String>,
+    /// Contact email appended to the User-Agent header
+    #[arg(long)]
+    contact: Option<String>,
+    /// Seconds before a whole request times out
+    #[arg(long, default_value_t = 30)]
+    timeout_secs: u64,
+    /// Seconds before connecting to the wiki times out
+    #[arg(long, default_value_t = 10)]
+    connect_timeout_secs: u64,
+    /// HTTP proxy url used for every request
+    #[arg(long)]
+    proxy: Option<String>,
+    /// Extra request header as "Name: value", can be repeated
+    #[arg(short = 'H', long = "header")]
+    headers: Vec<String>,
+    /// Maximum attempts per request, including the first one
+    #[arg(long)]
+    retry_attempts: Option<u32>,
+    /// Base delay in milliseconds of the exponential retry backoff
+    #[arg(long)]
+    retry_base_delay_ms: Option<u64>,
+    /// Randomized fraction (0.0 - 1.0) of every retry delay
+    #[arg(long)]
+    retry_jitter: Option<f64>,
+    /// Comma separated HTTP status codes that are retried
+    #[arg(long, value_delimiter = ',')]
+    retry_statuses: Option<Vec<u16>>,
+}
+
+impl MainArgs {
+    fn client_config(&self) -> ClientConfig {
+        ClientConfig {
+            user_agent: self.user_agent.clone(),
+            contact: self.contact.clone(),
+            timeout: Some(Duration::from_secs(self.timeout_secs)),
+            connect_timeout: Some(Duration::from_secs(self.connect_timeout_secs)),
+            proxy: self.proxy.clone(),
+            headers: self.headers.clone(),
+        }
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            max_attempts: self.retry_attempts.unwrap_or(default.max_attempts),
+            base_delay: self
+                .retry_base_delay_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default.base_delay),
+            jitter: self.retry_jitter.unwrap_or(default.jitter),
+            retryable_statuses: self
+                .retry_statuses
+                .clone()
+                .unwrap_or(default.retryable_statuses),
+            ..default
+        }
+    }
+
+    fn crawl_options(&self) -> CrawlOptions {
+        CrawlOptions {
+            max_depth: self.category_max_depth,
+            include: self.category_include.clone(),
+            exclude: self.category_exclude.clone(),
+            max_urls: self.category_max_urls,
+            concurrency: self.max_concurrency,
+        }
+    }
+
+    fn rate_limit(&self) -> RateLimit {
+        RateLimit {
+            requests_per_second: Some(self.requests_per_second).filter(|rps| *rps > 0.0),
+            max_concurrency: self.max_concurrency,
+            host_delay: Duration::from_millis(self.host_delay_ms),
+        }
+    }
 }
 
 #[tokio::main]
@@ -39,31 +175,111 @@ async fn main() {
     let args = MainArgs::parse();
 
     let base_url = "https://onepiece.fandom.com";
-    let output_dir = args.output_dir;
+    let output_dir = args.output_dir.clone();
     let category = args.category.as_ref();
 
-    let client = HttpClientWrapper(reqwest::Client::builder().build().unwrap());
-    let fetcher = HtmlFetcher::new(client, base_url);
-    let cat_crawler = Arc::new(CategoryScraper::new(fetcher.clone()));
+    // throttled below the retries, so every attempt waits for its own slot
+    let client = RetryClient::new(
+        RateLimitedClient::new(
+            HttpClientWrapper::from_config(&args.client_config()).unwrap(),
+            RateLimiter::new(args.rate_limit()),
+        ),
+        args.retry_policy(),
+    );
+    let retry_stats = client.stats();
+    let mut fetcher = match (&args.record, &args.replay) {
+        (_, Some(dir)) => HtmlFetcher::new(ReplayClient::new(dir), base_url),
+        (Some(dir), _) => HtmlFetcher::new(RecordingClient::new(client, dir), base_url),
+        _ => HtmlFetcher::new(client, base_url),
+    };
+    if let Some(cache_dir) = &args.cache_dir {
+        // pages served from the cache without a request would be missing from the recording
+        let ttl = match &args.record {
+            Some(_) => Duration::ZERO,
+            None => Duration::from_secs(args.cache_ttl_secs),
+        };
+        fetcher = fetcher.with_disk_cache(DiskCache::new(cache_dir, ttl));
+    }
+    let api = match args.source {
+        Source::Html => None,
+        Source::Api => Some(Arc::new(MediaWikiApi::new(fetcher.clone()))),
+    };
+    let cat_crawler: Arc<dyn UrlCrawler> = match &api {
+        Some(api) => api.clone(),
+        None => Arc::new(CategoryScraper::new(fetcher.clone())),
+    };
     let writer = OutputWriter::new(output_dir);
 
     if category.is_none() || category.is_some_and(|c| c == "df") {
-        let df_s = DfScraper::new(fetcher.clone());
-        let df_type_infos = df_s.get_dftype_info().await.unwrap();
-        let df_result = df_s.get_df_list().await.unwrap();
-        writer.write(&df_type_infos, "df_type_infos").await.unwrap();
-        writer.write(&df_result, "df_list").await.unwrap();
+        let df_s = DfScraper::new(fetcher.clone(), cat_crawler.clone())
+            .with_crawl_options(args.crawl_options());
+        let devil_fruits = async {
+            let df_type_infos = df_s.get_dftype_info().await?;
+            let df_result = df_s.get_df_list().await?;
+            df_type_infos
+                .iter()
+                .for_each(|info| info.check_counts(&df_result));
+            writer.write(&df_type_infos, "df_type_infos").await?;
+            writer.write(&df_result, "df_list").await
+        };
+        log_failure("devil fruits", devil_fruits.await);
+        let smile_users = async {
+            let smile_users = df_s.get_smile_users().await?;
+            writer.write(&smile_users, "smile_users").await
+        };
+        log_failure("SMILE users", smile_users.await);
     }
 
     if category.is_none() || category.is_some_and(|c| c == "pirate") {
-        let pirate_s = PirateScraper::new(fetcher.clone(), cat_crawler.clone());
-        let pirates = pirate_s.scrape().await.unwrap();
-        writer.write(&pirates, "pirates").await.unwrap();
+        let pirate_s = PirateScraper::new(fetcher.clone(), cat_crawler.clone())
+            .with_crawl_options(args.crawl_options());
+        let pirates = async {
+            let pirates = pirate_s.scrape().await?;
+            writer.write(&pirates, "pirates").await?;
+            let hierarchy = pirates::hierarchy::crew_hierarchy(&pirates);
+            writer.write(&hierarchy, "pirate_hierarchy").await
+        };
+        log_failure("pirates", pirates.await);
     }
 
     if category.is_none() || category.is_some_and(|c| c == "ship") {
-        let ship_s = ships::scraper::ShipScraper::new(fetcher.clone(), cat_crawler.clone());
-        let ships = ship_s.scrape().await.unwrap();
-        writer.write(&ships, "ships").await.unwrap();
+        let mut ship_s = ships::scraper::ShipScraper::new(fetcher.clone(), cat_crawler.clone())
+            .with_crawl_options(args.crawl_options());
+        if let Some(api) = &api {
+            ship_s = ship_s.with_page_source(api.clone());
+        }
+        let ships = async { writer.write(&ship_s.scrape().await?, "ships").await };
+        log_failure("ships", ships.await);
+    }
+
+    if category.is_none() || category.is_some_and(|c| c == "character") {
+        let character_s =
+            characters::scraper::CharacterScraper::new(fetcher.clone(), cat_crawler.clone())
+                .with_crawl_options(args.crawl_options());
+        let characters = async {
+            writer
+                .write(&character_s.scrape().await?, "characters")
+                .await
+        };
+        log_failure("characters", characters.await);
+    }
+
+    if category.is_none() || category.is_some_and(|c| c == "location") {
+        let location_s =
+            locations::scraper::LocationScraper::new(fetcher.clone(), cat_crawler.clone())
+                .with_crawl_options(args.crawl_options());
+        let locations = async { writer.write(&location_s.scrape().await?, "locations").await };
+        log_failure("locations", locations.await);
+    }
+
+    for (url, attempts) in retry_stats.retried() {
+        info!("{} took {} attempts", url, attempts);
+    }
+}
+
+/// A failing category is logged, the other categories are still scraped.
+fn log_failure(what: &str, result: Result<(), Error>) {
+    if let Err(e) = result {
+        error!("Error scraping {} {}", what, e);
     }
 }
@@ -0,0 +1,298 @@
+use async_trait::async_trait;
+use log::info;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{category::UrlCrawler, fetcher::HtmlFetcher, types::Error};
+
+use super::models::{path_to_title, CategoryMember, WikiPage};
+
+/// Structured access to wiki pages, independent of how the wiki renders them.
+#[async_trait]
+pub trait WikiSource: Send + Sync {
+    async fn page(&self, title: &str) -> Result<WikiPage, Error>;
+
+    async fn category_members(&self, category: &str) -> Result<Vec<CategoryMember>, Error>;
+}
+
+/// [`WikiSource`] backed by the MediaWiki `api.php` endpoints of the wiki.
+#[derive(Debug)]
+pub struct MediaWikiApi {
+    fetcher: HtmlFetcher,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ApiResult<T> {
+    Failure { error: ApiError },
+    Success(T),
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    code: String,
+    info: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParseResponse {
+    parse: ParsedPage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedPage {
+    title: String,
+    pageid: u64,
+    revid: u64,
+    text: String,
+    #[serde(default)]
+    categories: Vec<ParsedCategory>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedCategory {
+    category: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CategoryMembersResponse {
+    #[serde(rename = "continue")]
+    next: Option<CategoryMembersContinue>,
+    query: CategoryMembersQuery,
+}
+
+#[derive(Debug, Deserialize)]
+struct CategoryMembersContinue {
+    cmcontinue: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CategoryMembersQuery {
+    categorymembers: Vec<CategoryMember>,
+}
+
+fn api_path(params: &[(&str, &str)]) -> String {
+    let url = reqwest::Url::parse_with_params(
+        "https://localhost/api.php?format=json&formatversion=2",
+        params,
+    )
+    .unwrap();
+    format!("{}?{}", url.path(), url.query().unwrap_or_default())
+}
+
+fn not_found(path: &str) -> Error {
+    Error::HttpStatus {
+        url: path.to_string(),
+        status: 404,
+        retry_after: None,
+    }
+}
+
+impl MediaWikiApi {
+    pub fn new(fetcher: HtmlFetcher) -> Self {
+        Self { fetcher }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, params: &[(&str, &str)]) -> Result<T, Error> {
+        let path = api_path(params);
+        let body = self.fetcher.fetch_only(&path).await?;
+        let result = serde_json::from_str(&body).map_err(|e| Error::Decode {
+            url: path.clone(),
+            reason: e.to_string(),
+        })?;
+        match result {
+            ApiResult::Success(data) => Ok(data),
+            // a missing page is reported the same way the rendered wiki does
+            ApiResult::Failure { error } if error.code == "missingtitle" => Err(not_found(&path)),
+            ApiResult::Failure { error } => Err(Error::Request(format!(
+                "{}: {} on url: {}",
+                error.code, error.info, path
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl WikiSource for MediaWikiApi {
+    async fn page(&self, title: &str) -> Result<WikiPage, Error> {
+        let resp: ParseResponse = self
+            .call(&[
+                ("action", "parse"),
+                ("page", title),
+                ("prop", "text|categories|revid"),
+                ("redirects", "1"),
+            ])
+            .await?;
+        let page = resp.parse;
+        Ok(WikiPage {
+            title: page.title,
+            page_id: page.pageid,
+            revision_id: page.revid,
+            html: page.text,
+            categories: page
+                .categories
+                .into_iter()
+                .map(|c| c.category.replace('_', " "))
+                .collect(),
+        })
+    }
+
+    async fn category_members(&self, category: &str) -> Result<Vec<CategoryMember>, Error> {
+        let mut members = vec![];
+        let mut next: Option<String> = None;
+        loop {
+            let mut params = vec![
+                ("action", "query"),
+                ("list", "categorymembers"),
+                ("cmtitle", category),
+                ("cmlimit", "500"),
+            ];
+            if let Some(cont) = &next {
+                params.push(("cmcontinue", cont));
+            }
+            let resp: CategoryMembersResponse = self.call(&params).await?;
+            members.extend(resp.query.categorymembers);
+            match resp.next {
+                Some(cont) => next = Some(cont.cmcontinue),
+                None => break,
+            }
+        }
+        info!("{}: {} members", category, members.len());
+        Ok(members)
+    }
+}
+
+#[async_trait]
+impl UrlCrawler for MediaWikiApi {
+    async fn get_href(&self, path: &str) -> Result<Vec<String>, Error> {
+        let members = self.category_members(&path_to_title(path)).await?;
+        Ok(members.iter().map(CategoryMember::path).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use mockito::{Matcher, Server};
+
+    use crate::{
+        category::{CrawlOptions, UrlCrawler},
+        client::HttpClientWrapper,
+        fetcher::{mocks::prepare_fetcher, HtmlFetcher},
+    };
+
+    use super::{api_path, MediaWikiApi, WikiSource};
+
+    fn query(params: &[(&str, &str)]) -> Matcher {
+        Matcher::AllOf(
+            params
+                .iter()
+                .map(|(k, v)| Matcher::UrlEncoded(k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn page_from_mock_server() {
+        let mut server = Server::new_async().await;
+        let parse = server
+            .mock("GET", "/api.php")
+            .match_query(query(&[("action", "parse"), ("page", "Going Merry")]))
+            .with_body(
+                r#"{"parse":{"title":"Going Merry","pageid":1505,"revid":987654,
+                "text":"<div class=\"mw-parser-output\"><p>The Going Merry</p></div>",
+                "categories":[{"sortkey":"","category":"Straw_Hat_Pirates_Ships","hidden":false}]}}"#,
+            )
+            .create_async()
+            .await;
+        let missing = server
+            .mock("GET", "/api.php")
+            .match_query(query(&[("action", "parse"), ("page", "Nowhere")]))
+            .with_body(r#"{"error":{"code":"missingtitle","info":"The page you specified doesn't exist."}}"#)
+            .create_async()
+            .await;
+
+        let client = HttpClientWrapper(reqwest::Client::builder().build().unwrap());
+        let api = MediaWikiApi::new(HtmlFetcher::new(client, &server.url()));
+
+        let page = api.page("Going Merry").await.unwrap();
+        parse.assert_async().await;
+        assert_eq!(page.revision_id, 987654);
+        assert_eq!(page.categories, vec!["Straw Hat Pirates Ships"]);
+        assert!(page.html.contains("The Going Merry"));
+
+        assert!(api.page("Nowhere").await.unwrap_err().is_not_found());
+        missing.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn category_members_follow_continuation() {
+        fn members(cat: &str) -> Vec<(&str, &str)> {
+            vec![
+                ("action", "query"),
+                ("list", "categorymembers"),
+                ("cmtitle", cat),
+                ("cmlimit", "500"),
+            ]
+        }
+        let mut second_page = members("Category:Pirate Groups");
+        second_page.push(("cmcontinue", "page|2"));
+        let fetcher = prepare_fetcher([
+            (
+                api_path(&members("Category:Pirate Groups")),
+                Ok(r#"{"continue":{"cmcontinue":"page|2","continue":"-||"},"query":{"categorymembers":[
+                    {"pageid":1,"ns":0,"title":"Fallen Monk Pirates"},
+                    {"pageid":2,"ns":14,"title":"Category:New World Pirate Crews"}]}}"#
+                    .to_string()),
+            ),
+            (
+                api_path(&second_page),
+                Ok(r#"{"query":{"categorymembers":[{"pageid":3,"ns":0,"title":"Kid Pirates"}]}}"#
+                    .to_string()),
+            ),
+            (
+                api_path(&members("Category:New World Pirate Crews")),
+                Ok(r#"{"query":{"categorymembers":[
+                    {"pageid":4,"ns":0,"title":"Rocks Pirates"},
+                    {"pageid":1,"ns":0,"title":"Fallen Monk Pirates"},
+                    {"pageid":5,"ns":14,"title":"Category:Pirate Groups"}]}}"#
+                    .to_string()),
+            ),
+        ]);
+        let api = MediaWikiApi::new(fetcher);
+
+        let hrefs = api.get_href("/wiki/Category:Pirate_Groups").await.unwrap();
+        assert_eq!(
+            hrefs,
+            vec![
+                "/wiki/Fallen_Monk_Pirates",
+                "/wiki/Category:New_World_Pirate_Crews",
+                "/wiki/Kid_Pirates"
+            ]
+        );
+
+        let nested = api
+            .get_nested_href(
+                "/wiki/Category:Pirate_Groups",
+                &CrawlOptions::default(),
+                true,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            nested.keys().collect::<Vec<_>>(),
+            vec![
+                "/wiki/Fallen_Monk_Pirates",
+                "/wiki/Kid_Pirates",
+                "/wiki/Rocks_Pirates"
+            ]
+        );
+        assert_eq!(
+            nested["/wiki/Fallen_Monk_Pirates"],
+            BTreeSet::from([
+                "/wiki/Category:New_World_Pirate_Crews".to_string(),
+                "/wiki/Category:Pirate_Groups".to_string(),
+            ])
+        );
+    }
+}
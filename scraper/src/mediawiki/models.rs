@@ -0,0 +1,113 @@
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+
+/// characters MediaWiki keeps as they are in article paths (see `wfUrlencode`)
+const TITLE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b';')
+    .remove(b'@')
+    .remove(b'$')
+    .remove(b'!')
+    .remove(b'*')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b',')
+    .remove(b'/')
+    .remove(b'~')
+    .remove(b':');
+
+/// A page as returned by `action=parse`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WikiPage {
+    pub title: String,
+    pub page_id: u64,
+    pub revision_id: u64,
+    /// rendered content, without the fandom skin around it
+    pub html: String,
+    /// category names, without the `Category:` prefix
+    pub categories: Vec<String>,
+}
+
+impl WikiPage {
+    /// The page laid out like the rendered wiki, title and category links included, so the
+    /// html scrapers parse it as they parse a fetched page.
+    pub fn to_document(&self) -> String {
+        let categories = self
+            .categories
+            .iter()
+            .map(|c| {
+                let path = title_to_path(&format!("Category:{}", c));
+                format!(r#"<a href="{}">{}</a>"#, path, escape(c))
+            })
+            .collect::<String>();
+        format!(
+            r#"<main><span class="mw-page-title-main">{}</span><div class="page-header__categories">{}</div><div id="mw-content-text">{}</div></main>"#,
+            escape(&self.title),
+            categories,
+            self.html
+        )
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryMember {
+    #[serde(rename = "pageid")]
+    pub page_id: u64,
+    pub ns: i64,
+    pub title: String,
+}
+
+impl CategoryMember {
+    /// `/wiki/...` path of the member, encoded the same way as links on the rendered pages
+    pub fn path(&self) -> String {
+        title_to_path(&self.title)
+    }
+}
+
+pub fn title_to_path(title: &str) -> String {
+    format!(
+        "/wiki/{}",
+        utf8_percent_encode(&title.replace(' ', "_"), TITLE_ENCODE_SET)
+    )
+}
+
+/// inverse of [`title_to_path`]
+pub fn path_to_title(path: &str) -> String {
+    let title = path.strip_prefix("/wiki/").unwrap_or(path);
+    percent_decode_str(title)
+        .decode_utf8_lossy()
+        .replace('_', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{path_to_title, title_to_path, CategoryMember};
+
+    #[test]
+    fn titles_and_paths() {
+        assert_eq!(title_to_path("Going Merry"), "/wiki/Going_Merry");
+        assert_eq!(title_to_path("Monkey D. Luffy"), "/wiki/Monkey_D._Luffy");
+        assert_eq!(title_to_path("Category:Ships"), "/wiki/Category:Ships");
+        assert_eq!(title_to_path("Hanjōmaru"), "/wiki/Hanj%C5%8Dmaru");
+        assert_eq!(path_to_title("/wiki/Hanj%C5%8Dmaru"), "Hanjōmaru");
+        assert_eq!(
+            path_to_title("/wiki/Category:Pirate_Groups"),
+            "Category:Pirate Groups"
+        );
+
+        let member = CategoryMember {
+            page_id: 1,
+            ns: 14,
+            title: "Category:Grand Line Pirate Crews".to_string(),
+        };
+        assert_eq!(member.path(), "/wiki/Category:Grand_Line_Pirate_Crews");
+    }
+}
@@ -3,8 +3,11 @@ use serde_json::to_vec_pretty;
 use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 #[cfg(any(not(test), rust_analyzer))]
 use tokio::fs::{create_dir_all, File};
+#[cfg(any(not(test), rust_analyzer))]
 use tokio::io::AsyncWriteExt;
 
+use crate::types::Error;
+
 #[cfg(all(test, not(rust_analyzer)))]
 use mocks::{create_dir_all, File};
 
@@ -28,7 +31,7 @@ impl OutputWriter {
         }
     }
 
-    pub async fn write<T>(&self, data: &T, file_name: &str) -> std::io::Result<()>
+    pub async fn write<T>(&self, data: &T, file_name: &str) -> Result<(), Error>
     where
         T: ?Sized + Serialize,
     {
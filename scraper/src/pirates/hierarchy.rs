@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::Serialize;
+
+use crate::types::{NamedUrl, UrlTyped};
+
+use super::models::{AffiliationKind, Pirate};
+
+/// A crew and the crews serving under it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrewNode {
+    pub crew: NamedUrl,
+    pub subordinates: Vec<CrewNode>,
+}
+
+/// Trees of the subordinate affiliations of `pirates`, read from both sides of an affiliation.
+/// Crews without any subordinate or parent are left out.
+pub fn crew_hierarchy(pirates: &[Pirate]) -> Vec<CrewNode> {
+    let mut names = BTreeMap::new();
+    let mut children: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for pirate in pirates {
+        names.insert(pirate.get_path(), pirate.en_name.clone());
+    }
+    for pirate in pirates {
+        for affiliation in &pirate.affiliations {
+            let group = affiliation.group.get_path();
+            names
+                .entry(group.clone())
+                .or_insert_with(|| affiliation.group.name.clone());
+            let (parent, child) = match affiliation.kind {
+                AffiliationKind::Subordinate => (group, pirate.get_path()),
+                AffiliationKind::Parent => (pirate.get_path(), group),
+                _ => continue,
+            };
+            if parent != child {
+                children.entry(parent).or_default().insert(child);
+            }
+        }
+    }
+    let subordinates: HashSet<&String> = children.values().flatten().collect();
+    let roots = children.keys().filter(|crew| !subordinates.contains(crew));
+    let mut seen = HashSet::new();
+    let mut trees = vec![];
+    // crews only reachable through a loop get a tree of their own after the roots
+    for crew in roots.chain(children.keys()) {
+        if !seen.contains(crew) {
+            trees.push(build_node(crew, &names, &children, &mut seen));
+        }
+    }
+    trees
+}
+
+fn build_node(
+    crew: &str,
+    names: &BTreeMap<String, String>,
+    children: &BTreeMap<String, BTreeSet<String>>,
+    seen: &mut HashSet<String>,
+) -> CrewNode {
+    seen.insert(crew.to_string());
+    let mut subordinates = vec![];
+    for child in children.get(crew).into_iter().flatten() {
+        // a crew reached twice stays under the first parent walked
+        if !seen.contains(child) {
+            subordinates.push(build_node(child, names, children, seen));
+        }
+    }
+    CrewNode {
+        crew: NamedUrl::new(
+            names.get(crew).cloned().unwrap_or_default(),
+            crew.to_string(),
+        ),
+        subordinates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        pirates::models::{Affiliation, AffiliationKind, Pirate},
+        types::{NamedJpEn, NamedUrl},
+    };
+
+    use super::crew_hierarchy;
+
+    fn title(url: &str) -> String {
+        url.trim_start_matches("/wiki/").replace('_', " ")
+    }
+
+    fn pirate(url: &str, affiliations: &[(&str, AffiliationKind)]) -> Pirate {
+        Pirate::new(
+            NamedJpEn::new(String::new(), title(url), String::new()),
+            url.to_string(),
+            vec![],
+            vec![],
+            String::new(),
+            false,
+        )
+        .with_affiliations(
+            affiliations
+                .iter()
+                .map(|(group, kind)| Affiliation {
+                    group: NamedUrl::new(title(group), group.to_string()),
+                    kind: *kind,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn hierarchy_from_both_sides() {
+        let pirates = [
+            pirate(
+                "/wiki/Whitebeard_Pirates",
+                &[("/wiki/Spade_Pirates", AffiliationKind::Parent)],
+            ),
+            pirate(
+                "/wiki/Decalvan_Brothers",
+                &[("/wiki/Whitebeard_Pirates", AffiliationKind::Subordinate)],
+            ),
+            pirate(
+                "/wiki/Little_Oars_Jr.",
+                &[
+                    ("/wiki/Decalvan_Brothers", AffiliationKind::Subordinate),
+                    ("/wiki/Whitebeard_Pirates", AffiliationKind::Subordinate),
+                ],
+            ),
+            pirate(
+                "/wiki/Kid_Pirates",
+                &[("/wiki/Heart_Pirates", AffiliationKind::Alliance)],
+            ),
+            pirate(
+                "/wiki/Loop_A",
+                &[("/wiki/Loop_B", AffiliationKind::Subordinate)],
+            ),
+            pirate(
+                "/wiki/Loop_B",
+                &[("/wiki/Loop_A", AffiliationKind::Subordinate)],
+            ),
+        ];
+        let trees = crew_hierarchy(&pirates);
+        assert_eq!(trees.len(), 2);
+        let whitebeard = &trees[0];
+        assert_eq!(whitebeard.crew.name, "Whitebeard Pirates");
+        let subordinates = whitebeard
+            .subordinates
+            .iter()
+            .map(|n| n.crew.name.as_str())
+            .collect::<Vec<_>>();
+        // the Spade Pirates page is not scraped, its name comes from the link
+        assert_eq!(subordinates, vec!["Decalvan Brothers", "Spade Pirates"]);
+        assert_eq!(
+            whitebeard.subordinates[0].subordinates[0].crew.name,
+            "Little Oars Jr."
+        );
+        assert_eq!(trees[1].crew.name, "Loop A");
+        assert_eq!(trees[1].subordinates[0].crew.name, "Loop B");
+    }
+}
@@ -1,2 +1,4 @@
+pub mod hierarchy;
 pub mod models;
+pub mod parser;
 pub mod scraper;
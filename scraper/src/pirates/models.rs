@@ -1,16 +1,101 @@
 use serde::Serialize;
+use strum::{Display, EnumString};
 
-use crate::types::{NamedJpEn, NamedUrl, UrlTyped};
+use crate::types::{NamedJpEn, NamedUrl, Sea, UrlTyped};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString, Serialize)]
+#[strum(ascii_case_insensitive)]
+pub enum MemberStatus {
+    #[default]
+    Active,
+    Former,
+    Deceased,
+}
+
+impl MemberStatus {
+    /// status named in a free text such as `Former (deceased)`, deceased winning over former
+    pub fn from_text(text: &str) -> Self {
+        let text = text.to_lowercase();
+        if text.contains("deceased") {
+            MemberStatus::Deceased
+        } else if text.contains("former") {
+            MemberStatus::Former
+        } else {
+            MemberStatus::Active
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrewMember {
+    pub member: NamedUrl,
+    pub role: Option<String>,
+    pub status: MemberStatus,
+    pub non_canon: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, Serialize)]
+#[strum(ascii_case_insensitive)]
+pub enum CrewStatus {
+    Active,
+    Disbanded,
+    Defeated,
+}
+
+/// What a crew is to the group it lists as an affiliation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, Serialize)]
+#[strum(ascii_case_insensitive)]
+pub enum AffiliationKind {
+    /// the crew serves under the affiliated group
+    Subordinate,
+    Alliance,
+    FormerAlly,
+    /// listed without saying how the crew relates to the group
+    Affiliated,
+    FormerAffiliated,
+    /// the crew used to serve under the affiliated group
+    FormerSubordinate,
+    /// the affiliated group serves under the crew
+    Parent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Affiliation {
+    pub group: NamedUrl,
+    pub kind: AffiliationKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrewShip {
+    pub ship: NamedUrl,
+    /// false once the infobox marks the ship as former, destroyed...
+    pub current: bool,
+}
+
+impl CrewShip {
+    pub fn new(ship: NamedUrl, current: bool) -> Self {
+        Self { ship, current }
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct Pirate {
     pub name: String,
     pub en_name: String,
     pub description: String,
-    pub ship: Vec<NamedUrl>,
+    pub ship: Vec<CrewShip>,
     pub captain: Vec<NamedUrl>,
+    pub affiliations: Vec<Affiliation>,
+    pub bases: Vec<NamedUrl>,
+    pub members: Vec<CrewMember>,
     pub pic_url: String,
+    pub jolly_roger_url: Option<String>,
+    pub total_bounty: Option<u64>,
+    pub captain_bounty: Option<u64>,
+    pub status: Option<CrewStatus>,
     pub non_cannon: bool,
+    pub categories: Vec<String>,
+    pub seas: Vec<Sea>,
     url: String,
 }
 
@@ -18,7 +103,7 @@ impl Pirate {
     pub fn new(
         name_detail: NamedJpEn,
         url: String,
-        ship: Vec<NamedUrl>,
+        ship: Vec<CrewShip>,
         captain: Vec<NamedUrl>,
         pic_url: String,
         non_cannon: bool,
@@ -29,11 +114,57 @@ impl Pirate {
             description: name_detail.description,
             ship,
             captain,
+            affiliations: vec![],
+            bases: vec![],
+            members: vec![],
             pic_url,
+            jolly_roger_url: None,
+            total_bounty: None,
+            captain_bounty: None,
+            status: None,
             url,
             non_cannon,
+            categories: vec![],
+            seas: vec![],
         }
     }
+
+    pub fn with_categories(mut self, categories: Vec<String>) -> Self {
+        self.seas = Sea::from_categories(&categories);
+        self.categories = categories;
+        self
+    }
+
+    pub fn with_bounties(mut self, total: Option<u64>, captain: Option<u64>) -> Self {
+        self.total_bounty = total;
+        self.captain_bounty = captain;
+        self
+    }
+
+    pub fn with_jolly_roger(mut self, url: Option<String>) -> Self {
+        self.jolly_roger_url = url;
+        self
+    }
+
+    pub fn with_status(mut self, status: Option<CrewStatus>) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_affiliations(mut self, affiliations: Vec<Affiliation>) -> Self {
+        self.affiliations = affiliations;
+        self
+    }
+
+    pub fn with_bases(mut self, bases: Vec<NamedUrl>) -> Self {
+        self.bases = bases;
+        self
+    }
+
+    pub fn with_members(mut self, members: Vec<CrewMember>) -> Self {
+        self.members = members;
+        self
+    }
 }
 
 impl UrlTyped for Pirate {
@@ -67,6 +198,7 @@ mod tests {
     use super::*;
     use crate::types::{NamedJpEn, NamedUrl};
 
+    #[allow(clippy::too_many_arguments)]
     fn create_test_pirate(
         name: &str,
         en_name: &str,
@@ -85,7 +217,10 @@ mod tests {
                 description.to_string(),
             ),
             url.to_string(),
-            vec![NamedUrl::new(ship_name.to_string(), ship_url.to_string())],
+            vec![CrewShip::new(
+                NamedUrl::new(ship_name.to_string(), ship_url.to_string()),
+                true,
+            )],
             vec![NamedUrl::new(
                 captain_name.to_string(),
                 captain_url.to_string(),
@@ -144,4 +279,17 @@ mod tests {
         assert!(pirate2 > pirate1);
         assert_ne!(pirate1, pirate2);
     }
+
+    #[test]
+    fn member_status_from_text() {
+        assert_eq!(MemberStatus::from_text("Captain"), MemberStatus::Active);
+        assert_eq!(MemberStatus::from_text("Former"), MemberStatus::Former);
+        assert_eq!(
+            MemberStatus::from_text("former, Deceased"),
+            MemberStatus::Deceased
+        );
+        assert_eq!("former".parse(), Ok(MemberStatus::Former));
+        assert_eq!("Disbanded".parse(), Ok(CrewStatus::Disbanded));
+        assert!("Unknown".parse::<CrewStatus>().is_err());
+    }
 }
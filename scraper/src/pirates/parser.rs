@@ -0,0 +1,329 @@
+use itertools::Itertools as _;
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+use scraper::{ElementRef, Html};
+
+use crate::{
+    types::{Error, NamedUrl, UrlTyped},
+    utils::{self, Table},
+};
+
+use super::models::{Affiliation, AffiliationKind, CrewMember, MemberStatus};
+
+lazy_static! {
+    static ref REX_ANNOTATION: Regex = Regex::new(r"\(([^)]*)\)").unwrap();
+}
+
+const MEMBER_SECTIONS: [&str; 3] = ["Crew_Members", "Members", "Crew"];
+const NAME_COLUMNS: [&str; 3] = ["Name", "Member", "Crew Member"];
+const ROLE_COLUMNS: [&str; 4] = ["Position", "Role", "Occupation", "Occupations"];
+
+/// Roster of a crew page, read from the tables of its members section or, without any, from its list entries.
+pub fn parse_crew_members(html: &Html) -> Result<Vec<CrewMember>, Error> {
+    let Some(section) = MEMBER_SECTIONS
+        .iter()
+        .find_map(|id| utils::parse_section_elements(html, id))
+    else {
+        debug!("no crew members section");
+        return Ok(vec![]);
+    };
+    let table_selector = utils::parse_selector("table.wikitable")?;
+    let tables = section
+        .iter()
+        .flat_map(|el| {
+            std::iter::once(*el)
+                .filter(|e| e.value().name() == "table")
+                .chain(el.select(&table_selector))
+        })
+        .filter_map(|t| Table::from_element(t).ok())
+        .filter(|t| NAME_COLUMNS.iter().any(|c| t.column(c).is_ok()))
+        .collect_vec();
+
+    let members = if tables.is_empty() {
+        let entry_selector = utils::parse_selector("li")?;
+        section
+            .iter()
+            .flat_map(|el| el.select(&entry_selector))
+            .filter_map(|li| parse_member_entry(&li))
+            .collect_vec()
+    } else {
+        tables.iter().flat_map(parse_member_table).collect_vec()
+    };
+    Ok(members
+        .into_iter()
+        .unique_by(|m| m.member.get_path())
+        .collect())
+}
+
+/// Picture of the Jolly Roger, the infobox tab or image named after it.
+pub fn parse_jolly_roger_url(html: &Html) -> Result<Option<String>, Error> {
+    let is_jolly_roger = |text: &str| {
+        text.replace('_', " ")
+            .to_lowercase()
+            .contains("jolly roger")
+    };
+    let labels = html
+        .select(&utils::parse_selector(
+            "aside.portable-infobox .wds-tabs__tab-label, aside.portable-infobox .pi-image-collection-tabs li",
+        )?)
+        .map(|l| l.text().join(""))
+        .collect_vec();
+    let figures = html
+        .select(&utils::parse_selector(
+            "aside.portable-infobox figure.pi-image",
+        )?)
+        .collect_vec();
+    let image_selector = utils::parse_selector("a.image")?;
+    let url = figures
+        .iter()
+        .enumerate()
+        .find(|(i, figure)| {
+            let tab = (labels.len() == figures.len()).then(|| labels[*i].as_str());
+            let names = figure
+                .descendants()
+                .filter_map(|n| n.value().as_element())
+                .flat_map(|e| [e.attr("alt"), e.attr("data-image-name")])
+                .flatten()
+                .chain(tab)
+                .collect_vec();
+            names.into_iter().any(is_jolly_roger)
+        })
+        .and_then(|(_, figure)| figure.select(&image_selector).next())
+        .and_then(|a| a.value().attr("href"))
+        .and_then(|href| href.split("?cb=").next())
+        .map(String::from);
+    Ok(url)
+}
+
+/// Affiliations of an infobox field, `parent` when the field lists the groups serving under the crew.
+pub fn parse_affiliations(el: &ElementRef, parent: bool) -> Vec<Affiliation> {
+    utils::parse_infobox_entries(el)
+        .into_iter()
+        .filter_map(|entry| {
+            let former = entry.is_former();
+            let group = entry.link?;
+            let notes = entry.annotations.join(" ").to_lowercase();
+            let alliance = is_alliance(&notes) || is_alliance(&group.name.to_lowercase());
+            let kind = match (parent, notes.contains("subordinate"), alliance, former) {
+                (true, ..) => AffiliationKind::Parent,
+                (false, true, _, false) => AffiliationKind::Subordinate,
+                (false, true, _, true) => AffiliationKind::FormerSubordinate,
+                (false, false, true, false) => AffiliationKind::Alliance,
+                (false, false, true, true) => AffiliationKind::FormerAlly,
+                (false, false, false, false) => AffiliationKind::Affiliated,
+                (false, false, false, true) => AffiliationKind::FormerAffiliated,
+            };
+            Some(Affiliation { group, kind })
+        })
+        .collect()
+}
+
+fn is_alliance(text: &str) -> bool {
+    text.split(|c: char| !c.is_alphanumeric())
+        .any(|w| matches!(w, "ally" | "allies" | "allied" | "alliance"))
+}
+
+fn first_link(el: &ElementRef) -> Option<NamedUrl> {
+    let link = el
+        .select(&utils::parse_selector("a[href]").ok()?)
+        .find(|a| a.value().attr("href").is_some_and(|h| !h.starts_with('#')))?;
+    let name = link.text().join("").trim().to_string();
+    Some(NamedUrl::new(name, link.value().attr("href")?.to_string()))
+}
+
+fn is_non_canon(text: &str) -> bool {
+    text.to_lowercase().contains("non-canon")
+}
+
+fn parse_member_table(table: &Table) -> Vec<CrewMember> {
+    let column = |names: &[&str]| names.iter().find_map(|c| table.column(c).ok());
+    let name = column(&NAME_COLUMNS).unwrap_or_default();
+    let role = column(&ROLE_COLUMNS);
+    let status = column(&["Status"]);
+    table
+        .rows()
+        .iter()
+        .filter_map(|row| {
+            let member = first_link(&row.element(name)?)?;
+            let role = role.and_then(|c| row.text(c)).filter(|r| !r.is_empty());
+            let status = status.and_then(|c| row.text(c)).unwrap_or_default();
+            Some(CrewMember {
+                member,
+                non_canon: is_non_canon(&status),
+                status: MemberStatus::from_text(&status),
+                role,
+            })
+        })
+        .collect()
+}
+
+/// e.g. `Urouge: Captain (deceased)`
+fn parse_member_entry(li: &ElementRef) -> Option<CrewMember> {
+    let member = first_link(li)?;
+    let text = li.text().join("").split_whitespace().join(" ");
+    let annotations = REX_ANNOTATION
+        .captures_iter(&text)
+        .filter_map(|c| c.get(1))
+        .map(|m| m.as_str())
+        .join(" ");
+    let role = REX_ANNOTATION
+        .replace_all(&text.replacen(&member.name, "", 1), "")
+        .trim_matches(|c: char| c.is_whitespace() || ":-–,".contains(c))
+        .to_string();
+    Some(CrewMember {
+        member,
+        role: Some(role).filter(|r| !r.is_empty()),
+        status: MemberStatus::from_text(&annotations),
+        non_canon: is_non_canon(&annotations),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::Html;
+
+    use itertools::Itertools as _;
+
+    use crate::{
+        pirates::models::{AffiliationKind, MemberStatus},
+        utils,
+    };
+
+    use super::{parse_affiliations, parse_crew_members, parse_jolly_roger_url};
+
+    #[test]
+    fn affiliation_kinds() {
+        let doc = Html::parse_document(
+            r#"<div class="pi-data" data-source="affiliation"><div class="pi-data-value">
+            <a href="/wiki/Whitebeard_Pirates">Whitebeard Pirates</a> (subordinate)<br>
+            <a href="/wiki/Ninja-Pirate-Mink-Samurai_Alliance">Ninja-Pirate-Mink-Samurai Alliance</a><br>
+            <a href="/wiki/Kid_Pirates">Kid Pirates</a> (former allies)<br>
+            <a href="/wiki/Big_Mom_Pirates">Big Mom Pirates</a> (former subordinate)<br>
+            <a href="/wiki/Straw_Hat_Grand_Fleet">Straw Hat Grand Fleet</a> (allies)<br>
+            <a href="/wiki/Roger_Pirates">Roger Pirates</a> (former)
+            </div></div>"#,
+        );
+        let el = doc
+            .select(&utils::parse_selector(".pi-data").unwrap())
+            .next()
+            .unwrap();
+        let kinds = parse_affiliations(&el, false)
+            .into_iter()
+            .map(|a| a.kind)
+            .collect_vec();
+        assert_eq!(
+            kinds,
+            vec![
+                AffiliationKind::Subordinate,
+                AffiliationKind::Alliance,
+                AffiliationKind::FormerAlly,
+                AffiliationKind::FormerSubordinate,
+                AffiliationKind::Alliance,
+                AffiliationKind::FormerAffiliated,
+            ]
+        );
+        assert!(parse_affiliations(&el, true)
+            .iter()
+            .all(|a| a.kind == AffiliationKind::Parent));
+    }
+
+    #[test]
+    fn affiliation_without_annotation() {
+        let doc = Html::parse_document(
+            r#"<div class="pi-data" data-source="affiliation"><div class="pi-data-value">
+            <a href="/wiki/Baroque_Works">Baroque Works</a>
+            </div></div>"#,
+        );
+        let el = doc
+            .select(&utils::parse_selector(".pi-data").unwrap())
+            .next()
+            .unwrap();
+        let affiliations = parse_affiliations(&el, false);
+        assert_eq!(affiliations.len(), 1);
+        assert_eq!(affiliations[0].group.name, "Baroque Works");
+        assert_eq!(affiliations[0].kind, AffiliationKind::Affiliated);
+    }
+
+    #[test]
+    fn jolly_roger_from_tabs() {
+        let doc = Html::parse_document(
+            r#"<aside class="portable-infobox"><section class="wds-tabber">
+            <ul class="wds-tabs">
+                <li><div class="wds-tabs__tab-label">Crew</div></li>
+                <li><div class="wds-tabs__tab-label">Jolly Roger</div></li>
+            </ul>
+            <figure class="pi-item pi-image"><a href="/crew.png?cb=1" class="image"><img alt="Crew"></a></figure>
+            <figure class="pi-item pi-image"><a href="/flag.png?cb=2" class="image"><img alt="Flag"></a></figure>
+            </section></aside>"#,
+        );
+        assert_eq!(
+            parse_jolly_roger_url(&doc).unwrap().as_deref(),
+            Some("/flag.png")
+        );
+
+        let doc = Html::parse_document(
+            r#"<aside class="portable-infobox">
+            <figure class="pi-item pi-image"><a href="/crew.png" class="image"><img alt="Crew"></a></figure>
+            <figure class="pi-item pi-image"><a href="/jr.png" class="image"><img data-image-name="Kid_Pirates'_Jolly_Roger.png"></a></figure>
+            </aside>"#,
+        );
+        assert_eq!(
+            parse_jolly_roger_url(&doc).unwrap().as_deref(),
+            Some("/jr.png")
+        );
+
+        let doc = Html::parse_document(r#"<aside class="portable-infobox"></aside>"#);
+        assert_eq!(parse_jolly_roger_url(&doc).unwrap(), None);
+    }
+
+    #[test]
+    fn members_from_table() {
+        let doc = Html::parse_document(
+            r#"<div>
+            <h2><span id="Crew_Members">Crew Members</span></h2>
+            <table class="wikitable"><tbody>
+                <tr><th>Name</th><th>Position</th><th>Status</th></tr>
+                <tr><td><a href="/wiki/Urouge">Urouge</a></td><td>Captain</td><td>Active</td></tr>
+                <tr><td><a href="/wiki/Some_Monk">Some Monk</a></td><td>Combatant</td><td>Deceased</td></tr>
+                <tr><td><a href="/wiki/Movie_Monk">Movie Monk</a></td><td></td><td>Former (non-canon)</td></tr>
+                <tr><td>Unnamed monk</td><td>Cook</td><td></td></tr>
+            </tbody></table>
+            <h2><span id="History">History</span></h2>
+            <ul><li><a href="/wiki/Not_A_Member">Not a member</a></li></ul>
+            </div>"#,
+        );
+        let members = parse_crew_members(&doc).unwrap();
+        assert_eq!(members.len(), 3);
+        assert_eq!(members[0].member.name, "Urouge");
+        assert_eq!(members[0].role.as_deref(), Some("Captain"));
+        assert_eq!(members[1].status, MemberStatus::Deceased);
+        assert_eq!(members[2].role, None);
+        assert_eq!(members[2].status, MemberStatus::Former);
+        assert!(members[2].non_canon);
+        assert!(!members[0].non_canon);
+    }
+
+    #[test]
+    fn members_from_list() {
+        let doc = Html::parse_document(
+            r#"<div>
+            <h2><span id="Members">Members</span></h2>
+            <ul>
+                <li><a href="/wiki/Rocks_D._Xebec">Rocks D. Xebec</a>: Captain (deceased)</li>
+                <li><a href="/wiki/Edward_Newgate">Edward Newgate</a> - Combatant (former)</li>
+                <li><a href="/wiki/Edward_Newgate">Edward Newgate</a></li>
+            </ul>
+            </div>"#,
+        );
+        let members = parse_crew_members(&doc).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].role.as_deref(), Some("Captain"));
+        assert_eq!(members[0].status, MemberStatus::Deceased);
+        assert_eq!(members[1].role.as_deref(), Some("Combatant"));
+        assert_eq!(members[1].status, MemberStatus::Former);
+
+        let doc = Html::parse_document("<div><p>No roster</p></div>");
+        assert!(parse_crew_members(&doc).unwrap().is_empty());
+    }
+}
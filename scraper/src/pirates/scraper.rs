@@ -1,21 +1,25 @@
-use std::sync::Arc;
+use std::{str::FromStr as _, sync::Arc};
 
-use log::{error, info};
+use log::{error, info, warn};
 use scraper::Html;
-use tokio::{sync::Semaphore, task::JoinSet};
+use tokio::task::JoinSet;
 
 use crate::{
-    category::UrlCrawler,
+    category::{category_name, CrawlOptions, UrlCrawler},
     fetcher::HtmlFetcher,
-    types::{Error, NamedJpEn},
+    types::{Error, NamedJpEn, ParseContext},
     utils,
 };
 
-use super::models::Pirate;
+use super::{
+    models::{CrewShip, CrewStatus, Pirate},
+    parser::{parse_affiliations, parse_crew_members, parse_jolly_roger_url},
+};
 
 pub struct PirateScraper {
     fetcher: HtmlFetcher,
     category_crawler: Arc<dyn UrlCrawler>,
+    crawl_options: CrawlOptions,
 }
 
 impl PirateScraper {
@@ -23,25 +27,31 @@ impl PirateScraper {
         Self {
             fetcher,
             category_crawler,
+            crawl_options: CrawlOptions::default(),
         }
     }
 
+    pub fn with_crawl_options(mut self, options: CrawlOptions) -> Self {
+        self.crawl_options = options;
+        self
+    }
+
     pub async fn scrape(&self) -> Result<Vec<Pirate>, Error> {
         info!("crawling pirate categories");
-        let urls = self
+        let membership = self
             .category_crawler
-            .get_nested_href("/wiki/Category:Pirate_Groups", true)
+            .get_nested_href("/wiki/Category:Pirate_Groups", &self.crawl_options, true)
             .await?;
 
-        let concurrency_limit = Arc::new(Semaphore::new(20));
         let mut pirates = vec![];
         let mut pirate_tasks = JoinSet::new();
-        for url in urls {
-            let permit = concurrency_limit.clone().acquire_owned().await.unwrap();
+        for (url, categories) in membership {
             let fetcher = self.fetcher.clone();
+            let categories = categories.iter().map(|c| category_name(c)).collect();
             pirate_tasks.spawn(async move {
-                let _permit = permit;
-                let result = parse_pirate_detail(fetcher, url.clone()).await;
+                let result = parse_pirate_detail(fetcher, url.clone())
+                    .await
+                    .map(|pirate| pirate.with_categories(categories));
                 (url, result)
             });
         }
@@ -52,6 +62,9 @@ impl PirateScraper {
                 Ok((_, Ok(pirate))) => {
                     pirates.push(pirate);
                 }
+                Ok((url, Err(e))) if e.is_not_found() => {
+                    warn!("Pirate page {} no longer exists: {}", url, e)
+                }
                 Ok((url, Err(e))) => error!("Error parsing pirate detail at {}: {}", url, e),
                 Err(e) => error!("JoinSet error {}", e),
             }
@@ -64,16 +77,23 @@ impl PirateScraper {
 async fn parse_pirate_detail(fetcher: HtmlFetcher, pirate_url: String) -> Result<Pirate, Error> {
     let html = fetcher.fetch(&pirate_url).await.map(utils::cleanup_html)?;
     let doc = Html::parse_document(&html);
-    let pic_url = utils::parse_picture_url(&doc)?
+    let pic_url = utils::parse_picture_url(&doc)
+        .parse_field(&pirate_url, "picture")?
         .first()
         .cloned()
         .unwrap_or_default();
-    let description = utils::parse_main_page_first_paragraph(&doc)?;
-    let en_name = utils::parse_main_page_title(&doc)?;
-    let non_cannon = utils::parse_is_non_cannon(&doc)?;
+    let description =
+        utils::parse_main_page_first_paragraph(&doc).parse_field(&pirate_url, "description")?;
+    let en_name = utils::parse_main_page_title(&doc).parse_field(&pirate_url, "title")?;
+    let non_cannon = utils::parse_is_non_cannon(&doc).parse_field(&pirate_url, "categories")?;
     let mut name_detail = NamedJpEn::new(String::new(), en_name, description);
     let mut captain = vec![];
     let mut ship = vec![];
+    let mut total_bounty = None;
+    let mut captain_bounty = None;
+    let mut status = None;
+    let mut affiliations = vec![];
+    let mut bases = vec![];
     let stat_selector = utils::parse_selector("aside.portable-infobox>section .pi-data")?;
     for el in doc.select(&stat_selector) {
         if let Some(kind) = el.attr("data-source") {
@@ -85,36 +105,45 @@ async fn parse_pirate_detail(fetcher: HtmlFetcher, pirate_url: String) -> Result
                 "captain" | "extra1" => {
                     captain.extend(utils::parse_infobox_single_data_named_urls(&el))
                 }
-                "ship" => {
-                    // edge case for strawhat, take 0 & 5 only, preventing unrelated <a> tag
-                    let named_urls = utils::parse_infobox_single_data_named_urls(&el);
-                    ship.extend(
-                        named_urls
-                            .into_iter()
-                            .enumerate()
-                            .filter_map(
-                                |(i, _named)| {
-                                    if i == 0 || i == 5 {
-                                        Some(_named)
-                                    } else {
-                                        None
-                                    }
-                                },
-                            ),
-                    );
+                "ship" => ship.extend(utils::parse_infobox_entries(&el).into_iter().filter_map(
+                    |entry| {
+                        let current = !entry.is_former();
+                        entry.link.map(|link| CrewShip::new(link, current))
+                    },
+                )),
+                "bounty" | "total bounty" | "tbounty" => {
+                    total_bounty = utils::parse_infobox_single_data_text(&el)
+                        .and_then(|text| utils::parse_berries(&text));
+                }
+                "cbounty" | "captain bounty" => {
+                    captain_bounty = utils::parse_infobox_single_data_text(&el)
+                        .and_then(|text| utils::parse_berries(&text));
+                }
+                "affiliation" | "affiliations" => {
+                    affiliations.extend(parse_affiliations(&el, false))
+                }
+                "subordinates" | "subcrews" => affiliations.extend(parse_affiliations(&el, true)),
+                "base" | "bases" => bases.extend(utils::parse_infobox_links(&el)),
+                "status" => {
+                    status = utils::parse_infobox_entries(&el)
+                        .first()
+                        .and_then(|entry| CrewStatus::from_str(&entry.text).ok());
                 }
                 _ => {}
             }
         }
     }
-    Ok(Pirate::new(
-        name_detail,
-        pirate_url,
-        ship,
-        captain,
-        pic_url,
-        non_cannon,
-    ))
+    let jolly_roger = parse_jolly_roger_url(&doc).parse_field(&pirate_url, "jolly roger")?;
+    let members = parse_crew_members(&doc).parse_field(&pirate_url, "members")?;
+    Ok(
+        Pirate::new(name_detail, pirate_url, ship, captain, pic_url, non_cannon)
+            .with_members(members)
+            .with_affiliations(affiliations)
+            .with_bases(bases)
+            .with_bounties(total_bounty, captain_bounty)
+            .with_jolly_roger(jolly_roger)
+            .with_status(status),
+    )
 }
 
 #[cfg(test)]
@@ -123,7 +152,13 @@ mod tests {
     use std::sync::Arc;
 
     use crate::{
-        category::CategoryScraper, fetcher::mocks::prepare_fetcher, pirates::scraper::PirateScraper,
+        category::CategoryScraper,
+        fetcher::mocks::prepare_fetcher,
+        pirates::{
+            models::CrewStatus,
+            scraper::{parse_pirate_detail, PirateScraper},
+        },
+        types::{Error, Sea, UrlTyped},
     };
 
     #[tokio::test]
@@ -204,6 +239,15 @@ mod tests {
                     <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="ship">
                         <div class="pi-data-value pi-font"><a href="/wiki/Hanjomaru" title="Hanjomaru">Hanjomaru</a></div>
                     </div>
+                    <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="bounty">
+                        <div class="pi-data-value pi-font"><img alt="Berry"/> 108,000,000</div>
+                    </div>
+                    <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="status">
+                        <div class="pi-data-value pi-font">Active</div>
+                    </div>
+                    <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="base">
+                        <div class="pi-data-value pi-font"><a href="/wiki/Sky_Island">Sky Island</a></div>
+                    </div>
                 </section>
             </aside>
             <p></p>
@@ -244,5 +288,46 @@ mod tests {
         let scraper = PirateScraper::new(fetcher, Arc::new(cat_crawler));
         let pirates = scraper.scrape().await.unwrap();
         assert_eq!(pirates.len(), 2);
+        assert_eq!(pirates[0].categories, vec!["Grand Line Pirate Crews"]);
+        assert_eq!(pirates[0].seas, vec![Sea::GrandLine]);
+        assert_eq!(pirates[0].ship.len(), 1);
+        assert_eq!(pirates[0].ship[0].ship.name, "Hanjomaru");
+        assert!(pirates[0].ship[0].current);
+        assert_eq!(pirates[0].total_bounty, Some(108_000_000));
+        assert_eq!(pirates[0].status, Some(CrewStatus::Active));
+        assert_eq!(pirates[1].total_bounty, None);
+        assert_eq!(pirates[0].bases[0].get_path(), "/wiki/Sky_Island");
+        assert_eq!(pirates[1].seas, vec![Sea::NewWorld]);
+    }
+
+    #[tokio::test]
+    async fn layout_change_is_a_parse_error() {
+        let fetcher = prepare_fetcher([
+            (
+                "/wiki/Kid_Pirates".to_string(),
+                Ok(r##"<main><div id="mw-content-text"><p></p><p></p><p>No title</p></div></main>"##.to_string()),
+            ),
+            (
+                "/wiki/Gone_Pirates".to_string(),
+                Err(Error::HttpStatus {
+                    url: "/wiki/Gone_Pirates".to_string(),
+                    status: 404,
+                    retry_after: None,
+                }),
+            ),
+        ]);
+
+        let err = parse_pirate_detail(fetcher.clone(), "/wiki/Kid_Pirates".to_string())
+            .await
+            .unwrap_err();
+        assert!(!err.is_not_found());
+        assert!(
+            matches!(err, Error::Parse { field, reason, .. } if field == "title" && reason.contains("title"))
+        );
+
+        let err = parse_pirate_detail(fetcher, "/wiki/Gone_Pirates".to_string())
+            .await
+            .unwrap_err();
+        assert!(err.is_not_found());
     }
 }
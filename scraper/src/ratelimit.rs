@@ -0,0 +1,247 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::{sleep_until, Instant},
+};
+
+use crate::{
+    client::{FetchHtml, Page, Validators},
+    types::Error,
+};
+
+/// Crawling limits shared by every request going through one [`RateLimitedClient`].
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    /// `None` disables the global request rate limit
+    pub requests_per_second: Option<f64>,
+    pub max_concurrency: usize,
+    /// minimum delay between two requests to the same host
+    pub host_delay: Duration,
+}
+
+#[derive(Debug)]
+struct Schedule {
+    next: Instant,
+    hosts: HashMap<String, Instant>,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    host_delay: Duration,
+    permits: Semaphore,
+    schedule: Mutex<Schedule>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        let interval = limit
+            .requests_per_second
+            .filter(|rps| *rps > 0.0)
+            .map(|rps| Duration::from_secs_f64(1.0 / rps))
+            .unwrap_or_default();
+        Self {
+            interval,
+            host_delay: limit.host_delay,
+            permits: Semaphore::new(limit.max_concurrency.max(1)),
+            schedule: Mutex::new(Schedule {
+                next: Instant::now(),
+                hosts: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Waits for a free request slot for `url`, the request may run while the permit is held.
+    pub async fn acquire(&self, url: &str) -> SemaphorePermit<'_> {
+        let permit = self.permits.acquire().await.unwrap();
+        let slot = self.reserve(&host_of(url));
+        sleep_until(slot).await;
+        permit
+    }
+
+    /// books the earliest slot allowed by both the global rate and the host delay
+    fn reserve(&self, host: &str) -> Instant {
+        let mut schedule = self.schedule.lock().unwrap();
+        let mut slot = Instant::now().max(schedule.next);
+        if let Some(host_next) = schedule.hosts.get(host) {
+            slot = slot.max(*host_next);
+        }
+        schedule.next = slot + self.interval;
+        schedule
+            .hosts
+            .insert(host.to_string(), slot + self.host_delay);
+        slot
+    }
+}
+
+/// [`FetchHtml`] decorator throttling every request it sends.
+///
+/// Wrapped by a [`crate::retry::RetryClient`], each retry waits for its own slot and no permit
+/// is held while the retry backs off.
+#[derive(Debug)]
+pub struct RateLimitedClient<C> {
+    inner: C,
+    limiter: RateLimiter,
+}
+
+impl<C: FetchHtml> RateLimitedClient<C> {
+    pub fn new(inner: C, limiter: RateLimiter) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl<C: FetchHtml> FetchHtml for RateLimitedClient<C> {
+    async fn fetch(&self, url: String) -> Result<String, Error> {
+        let _permit = self.limiter.acquire(&url).await;
+        self.inner.fetch(url).await
+    }
+
+    async fn fetch_page(
+        &self,
+        url: String,
+        validators: Option<Validators>,
+    ) -> Result<Option<Page>, Error> {
+        let _permit = self.limiter.acquire(&url).await;
+        self.inner.fetch_page(url, validators).await
+    }
+}
+
+fn host_of(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use tokio::{task::JoinSet, time::Instant};
+
+    use crate::{
+        client::FetchHtml,
+        retry::{RetryClient, RetryPolicy},
+        types::Error,
+    };
+
+    use super::{RateLimit, RateLimitedClient, RateLimiter};
+
+    /// fails with a 503 until it was called `fails` times
+    #[derive(Debug)]
+    struct FlakyClient {
+        fails: usize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl FetchHtml for FlakyClient {
+        async fn fetch(&self, url: String) -> Result<String, Error> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.fails {
+                return Err(Error::HttpStatus {
+                    url,
+                    status: 503,
+                    retry_after: None,
+                });
+            }
+            Ok("Will of D".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn limits_concurrency() {
+        let limiter = Arc::new(RateLimiter::new(RateLimit {
+            requests_per_second: None,
+            max_concurrency: 2,
+            host_delay: Duration::ZERO,
+        }));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let mut tasks = JoinSet::new();
+        for _ in 0..6 {
+            let (limiter, in_flight, peak) = (limiter.clone(), in_flight.clone(), peak.clone());
+            tasks.spawn(async move {
+                let _permit = limiter
+                    .acquire("https://onepiece.fandom.com/wiki/Zoan")
+                    .await;
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+        tasks.join_all().await;
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn spaces_requests() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_second: Some(100.0),
+            max_concurrency: 10,
+            host_delay: Duration::ZERO,
+        });
+        let start = Instant::now();
+        for _ in 0..5 {
+            let _permit = limiter.acquire("/wiki/Zoan").await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn host_delay_is_per_host() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_second: None,
+            max_concurrency: 10,
+            host_delay: Duration::from_millis(50),
+        });
+        let start = Instant::now();
+        let a = limiter.reserve("onepiece.fandom.com");
+        let b = limiter.reserve("static.wikia.nocookie.net");
+        let c = limiter.reserve("onepiece.fandom.com");
+        assert!(a - start < Duration::from_millis(10));
+        assert!(b - start < Duration::from_millis(10));
+        assert!(c - a >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn retries_are_throttled() {
+        let limited = RateLimitedClient::new(
+            FlakyClient {
+                fails: 2,
+                calls: AtomicUsize::new(0),
+            },
+            RateLimiter::new(RateLimit {
+                requests_per_second: Some(20.0),
+                max_concurrency: 1,
+                host_delay: Duration::ZERO,
+            }),
+        );
+        let client = RetryClient::new(
+            limited,
+            RetryPolicy {
+                base_delay: Duration::from_millis(1),
+                jitter: 0.0,
+                ..Default::default()
+            },
+        );
+        let start = Instant::now();
+        assert_eq!(
+            client.fetch("/wiki/Zoan".to_string()).await.unwrap(),
+            "Will of D"
+        );
+        // three attempts, each one waited for its own slot
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
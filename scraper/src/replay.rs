@@ -0,0 +1,323 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use log::error;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    cache::file_name,
+    client::{FetchHtml, Page, Validators},
+    types::Error,
+    utils,
+};
+
+/// A recorded response, error statuses included so a replay fails where the live run did.
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    url: String,
+    status: u16,
+    body: String,
+}
+
+/// the host is part of the key, recordings of a mock server and of the wiki do not collide
+fn recording_path(dir: &Path, url: &str) -> PathBuf {
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| {
+            let host = u.host_str()?.to_string();
+            Some(match u.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host,
+            })
+        })
+        .unwrap_or_default();
+    let key = format!("{}{}", host, utils::to_url_path(url));
+    dir.join(format!("{}.json", file_name(&key)))
+}
+
+/// [`FetchHtml`] decorator writing every response into a fixture directory.
+#[derive(Debug)]
+pub struct RecordingClient<C> {
+    inner: C,
+    dir: PathBuf,
+}
+
+impl<C: FetchHtml> RecordingClient<C> {
+    pub fn new(inner: C, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+
+    async fn record(&self, recording: &Recording) -> Result<(), Error> {
+        fs::create_dir_all(&self.dir).await?;
+        let bytes = serde_json::to_vec_pretty(recording)
+            .map_err(|e| Error::Io(format!("{} on {}", e, &recording.url)))?;
+        let path = recording_path(&self.dir, &recording.url);
+        // same as the disk cache, a crash mid-write must not leave a truncated fixture behind
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, bytes).await?;
+        fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn record_response(&self, url: String, response: Result<&str, &Error>) {
+        let recording = match response {
+            Ok(body) => Recording {
+                url,
+                status: 200,
+                body: body.to_string(),
+            },
+            Err(Error::HttpStatus { status, .. }) => Recording {
+                url,
+                status: *status,
+                body: String::new(),
+            },
+            // transport failures say nothing about the page, nothing to replay
+            Err(_) => return,
+        };
+        if let Err(e) = self.record(&recording).await {
+            error!("Failed recording {}: {}", &recording.url, e);
+        }
+    }
+}
+
+#[async_trait]
+impl<C: FetchHtml> FetchHtml for RecordingClient<C> {
+    async fn fetch(&self, url: String) -> Result<String, Error> {
+        let result = self.inner.fetch(url.clone()).await;
+        self.record_response(url, result.as_deref()).await;
+        result
+    }
+
+    async fn fetch_page(
+        &self,
+        url: String,
+        validators: Option<Validators>,
+    ) -> Result<Option<Page>, Error> {
+        // a not modified reply has no body to record, so revalidate only pages already archived
+        let recorded = fs::try_exists(recording_path(&self.dir, &url))
+            .await
+            .unwrap_or(false);
+        let validators = validators.filter(|_| recorded);
+        let result = self.inner.fetch_page(url.clone(), validators).await;
+        match &result {
+            Ok(Some(page)) => self.record_response(url, Ok(&page.body)).await,
+            Ok(None) => {}
+            Err(e) => self.record_response(url, Err(e)).await,
+        }
+        result
+    }
+}
+
+/// [`FetchHtml`] serving the responses captured by [`RecordingClient`], without any network access.
+#[derive(Debug)]
+pub struct ReplayClient {
+    dir: PathBuf,
+}
+
+impl ReplayClient {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl FetchHtml for ReplayClient {
+    async fn fetch(&self, url: String) -> Result<String, Error> {
+        let bytes = fs::read(recording_path(&self.dir, &url))
+            .await
+            .map_err(|_| Error::Request(format!("No recorded response on url: {}", url)))?;
+        let recording: Recording = serde_json::from_slice(&bytes).map_err(|e| Error::Decode {
+            url: url.clone(),
+            reason: e.to_string(),
+        })?;
+        if recording.status != 200 {
+            return Err(Error::HttpStatus {
+                url,
+                status: recording.status,
+                retry_after: None,
+            });
+        }
+        Ok(recording.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    };
+
+    use async_trait::async_trait;
+
+    use crate::{
+        cache::mocks::temp_dir,
+        category::CategoryScraper,
+        client::{FetchHtml, Page, Validators},
+        fetcher::{mocks::prepare_client, HtmlFetcher},
+        ships::scraper::ShipScraper,
+        types::Error,
+    };
+
+    use super::{recording_path, RecordingClient, ReplayClient};
+
+    #[test]
+    fn recording_path_keeps_host() {
+        let dir = Path::new("fixtures");
+        let wiki = recording_path(
+            dir,
+            "https://onepiece.fandom.com/wiki/Category:Ships?from=B",
+        );
+        assert_eq!(
+            wiki,
+            recording_path(
+                dir,
+                "https://onepiece.fandom.com/wiki/Category:Ships?from=B"
+            )
+        );
+        assert_ne!(
+            wiki,
+            recording_path(dir, "http://127.0.0.1:1234/wiki/Category:Ships?from=B")
+        );
+        assert_ne!(wiki, recording_path(dir, "/wiki/Category:Ships?from=B"));
+    }
+
+    #[tokio::test]
+    async fn record_then_replay() {
+        let dir = temp_dir("record-then-replay");
+        let live = RecordingClient::new(
+            prepare_client([
+                (
+                    "/wiki/Zoan".to_string(),
+                    Ok("<html>zoan</html>".to_string()),
+                ),
+                (
+                    "/wiki/Gone".to_string(),
+                    Err(Error::HttpStatus {
+                        url: "/wiki/Gone".to_string(),
+                        status: 404,
+                        retry_after: None,
+                    }),
+                ),
+            ]),
+            &dir,
+        );
+        assert!(live.fetch("/wiki/Zoan".to_string()).await.is_ok());
+        assert!(live.fetch("/wiki/Gone".to_string()).await.is_err());
+        assert!(live.fetch("/wiki/Offline".to_string()).await.is_err());
+
+        let replay = ReplayClient::new(&dir);
+        assert_eq!(
+            replay.fetch("/wiki/Zoan".to_string()).await.unwrap(),
+            "<html>zoan</html>"
+        );
+        assert!(replay
+            .fetch("/wiki/Gone".to_string())
+            .await
+            .unwrap_err()
+            .is_not_found());
+        assert!(matches!(
+            replay.fetch("/wiki/Offline".to_string()).await,
+            Err(Error::Request(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// answers "not modified" to every conditional request and logs whether validators were sent
+    #[derive(Debug, Default)]
+    struct ConditionalClient {
+        conditional: Mutex<Vec<bool>>,
+    }
+
+    #[async_trait]
+    impl FetchHtml for ConditionalClient {
+        async fn fetch(&self, _url: String) -> Result<String, Error> {
+            Ok("<html>zoan</html>".to_string())
+        }
+
+        async fn fetch_page(
+            &self,
+            url: String,
+            validators: Option<Validators>,
+        ) -> Result<Option<Page>, Error> {
+            self.conditional.lock().unwrap().push(validators.is_some());
+            if validators.is_some() {
+                return Ok(None);
+            }
+            Ok(Some(Page {
+                body: self.fetch(url).await?,
+                validators: Validators::default(),
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn record_conditional_fetches() {
+        let dir = temp_dir("record-conditional-fetches");
+        let live = RecordingClient::new(ConditionalClient::default(), &dir);
+        let validators = Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+
+        // not archived yet, the full page is requested so there is a body to record
+        let page = live
+            .fetch_page("/wiki/Zoan".to_string(), Some(validators.clone()))
+            .await
+            .unwrap();
+        assert_eq!(page.unwrap().body, "<html>zoan</html>");
+
+        // archived, the page is revalidated and the recording kept
+        let page = live
+            .fetch_page("/wiki/Zoan".to_string(), Some(validators))
+            .await
+            .unwrap();
+        assert!(page.is_none());
+        assert_eq!(*live.inner.conditional.lock().unwrap(), vec![false, true]);
+
+        let replay = ReplayClient::new(&dir);
+        assert_eq!(
+            replay.fetch("/wiki/Zoan".to_string()).await.unwrap(),
+            "<html>zoan</html>"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replay_a_whole_scrape() {
+        let dir = temp_dir("replay-a-whole-scrape");
+        let live = HtmlFetcher::new(
+            RecordingClient::new(
+                prepare_client([
+                    (
+                        "/wiki/Category:Ships".to_string(),
+                        Ok(r##"<ul><li class="category-page__member"><a href="/wiki/Hanjomaru" class="category-page__member-link">Hanjomaru</a></li></ul>"##.to_string()),
+                    ),
+                    (
+                        "/wiki/Hanjomaru".to_string(),
+                        Ok(r##"<main><span class="mw-page-title-main">Hanjomaru</span><div id="mw-content-text"><p></p><p></p><p>Hanjomaru is a ship.</p></div></main>"##.to_string()),
+                    ),
+                ]),
+                &dir,
+            ),
+            "",
+        );
+        let crawler = Arc::new(CategoryScraper::new(live.clone()));
+        let recorded = ShipScraper::new(live, crawler).scrape().await.unwrap();
+
+        let offline = HtmlFetcher::new(ReplayClient::new(&dir), "");
+        let crawler = Arc::new(CategoryScraper::new(offline.clone()));
+        let replayed = ShipScraper::new(offline, crawler).scrape().await.unwrap();
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(recorded, replayed);
+        assert_eq!(replayed[0].description, "Hanjomaru is a ship.");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
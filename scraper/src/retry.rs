@@ -0,0 +1,305 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use log::warn;
+use rand::Rng as _;
+
+use crate::{
+    client::{FetchHtml, Page, Validators},
+    types::Error,
+};
+
+/// How [`RetryClient`] decides whether and when to try a request again.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// total attempts per url, including the first one
+    pub max_attempts: u32,
+    /// delay before the first retry, doubled on every following retry
+    pub base_delay: Duration,
+    /// upper bound of a single backoff delay
+    pub max_delay: Duration,
+    /// fraction (0.0 - 1.0) of the backoff delay that is randomized
+    pub jitter: f64,
+    pub retryable_statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            retryable_statuses: vec![408, 429, 500, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn is_retryable(&self, err: &Error) -> bool {
+        match err {
+            Error::HttpStatus { status, .. } => self.retryable_statuses.contains(status),
+            Error::Request(_) | Error::Timeout(_) => true,
+            _ => false,
+        }
+    }
+
+    /// `attempt` is 1-based
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return exp;
+        }
+        exp.mul_f64(1.0 - jitter * rand::rng().random::<f64>())
+    }
+
+    /// `Retry-After` wins over the computed backoff on 429 and 503, still bounded by `max_delay`
+    fn delay_for(&self, err: &Error, attempt: u32) -> Duration {
+        match err {
+            Error::HttpStatus {
+                status: 429 | 503,
+                retry_after: Some(secs),
+                ..
+            } => Duration::from_secs(*secs).min(self.max_delay),
+            _ => self.backoff(attempt),
+        }
+    }
+}
+
+/// Number of attempts each url took, shared between clones.
+#[derive(Debug, Clone, Default)]
+pub struct RetryStats(Arc<Mutex<HashMap<String, u32>>>);
+
+impl RetryStats {
+    fn record(&self, url: &str, attempts: u32) {
+        self.0.lock().unwrap().insert(url.to_string(), attempts);
+    }
+
+    pub fn retried(&self) -> Vec<(String, u32)> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, n)| **n > 1)
+            .map(|(url, n)| (url.clone(), *n))
+            .collect()
+    }
+}
+
+/// [`FetchHtml`] decorator retrying failed requests with exponential backoff and jitter.
+#[derive(Debug)]
+pub struct RetryClient<C> {
+    inner: C,
+    policy: RetryPolicy,
+    stats: RetryStats,
+}
+
+impl<C: FetchHtml> RetryClient<C> {
+    pub fn new(inner: C, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            stats: RetryStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> RetryStats {
+        self.stats.clone()
+    }
+
+    async fn with_retry<T, F, Fut>(&self, url: &str, request: F) -> Result<T, Error>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let max_attempts = self.policy.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let result = request().await;
+            match result {
+                Err(e) if attempt < max_attempts && self.policy.is_retryable(&e) => {
+                    let delay = self.policy.delay_for(&e, attempt);
+                    warn!(
+                        "attempt {}/{} failed for {}: {}. retrying in {:?}",
+                        attempt, max_attempts, url, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => {
+                    self.stats.record(url, attempt);
+                    return result;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<C: FetchHtml> FetchHtml for RetryClient<C> {
+    async fn fetch(&self, url: String) -> Result<String, Error> {
+        self.with_retry(&url, || self.inner.fetch(url.clone()))
+            .await
+    }
+
+    async fn fetch_page(
+        &self,
+        url: String,
+        validators: Option<Validators>,
+    ) -> Result<Option<Page>, Error> {
+        self.with_retry(&url, || {
+            self.inner.fetch_page(url.clone(), validators.clone())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Mutex,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+
+    use crate::{client::FetchHtml, types::Error};
+
+    use super::{RetryClient, RetryPolicy};
+
+    /// replies with the scripted results in order, panics when called once more
+    #[derive(Debug)]
+    struct ScriptedClient {
+        responses: Mutex<VecDeque<Result<String, Error>>>,
+        calls: AtomicU32,
+    }
+
+    impl ScriptedClient {
+        fn new<const N: usize>(responses: [Result<String, Error>; N]) -> Self {
+            Self {
+                responses: Mutex::new(VecDeque::from(responses)),
+                calls: AtomicU32::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl FetchHtml for ScriptedClient {
+        async fn fetch(&self, _url: String) -> Result<String, Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected request")
+        }
+    }
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            jitter: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn status(status: u16, retry_after: Option<u64>) -> Error {
+        Error::HttpStatus {
+            url: "url".to_string(),
+            status,
+            retry_after,
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let mock = ScriptedClient::new([
+            Err(status(503, None)),
+            Err(status(502, None)),
+            Ok("Will of D".to_string()),
+        ]);
+
+        let client = RetryClient::new(mock, fast_policy(4));
+        let resp = client.fetch("url".to_string()).await;
+        assert_eq!(resp.unwrap(), "Will of D");
+        assert_eq!(client.inner.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(client.stats().retried(), vec![("url".to_string(), 3)]);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let reset = || Err(Error::Request("connection reset".to_string()));
+        let mock = ScriptedClient::new([reset(), reset(), reset()]);
+
+        let client = RetryClient::new(mock, fast_policy(3));
+        let resp = client.fetch("url".to_string()).await;
+        assert!(matches!(resp, Err(Error::Request(_))));
+        assert_eq!(client.stats().retried(), vec![("url".to_string(), 3)]);
+    }
+
+    #[tokio::test]
+    async fn non_retryable_status_fails_fast() {
+        let mock = ScriptedClient::new([Err(status(404, None))]);
+
+        let client = RetryClient::new(mock, fast_policy(4));
+        let resp = client.fetch("url".to_string()).await;
+        assert!(matches!(resp, Err(Error::HttpStatus { status: 404, .. })));
+        assert_eq!(client.inner.calls.load(Ordering::SeqCst), 1);
+        assert!(client.stats().retried().is_empty());
+    }
+
+    #[test]
+    fn backoff_delays() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+            jitter: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(
+            policy.delay_for(&status(500, Some(7)), 2),
+            Duration::from_millis(200)
+        );
+        assert_eq!(
+            policy.delay_for(&status(429, Some(7)), 1),
+            Duration::from_millis(350)
+        );
+
+        let patient = RetryPolicy {
+            max_delay: Duration::from_secs(5),
+            ..policy.clone()
+        };
+        assert_eq!(
+            patient.delay_for(&status(503, Some(2)), 3),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            patient.delay_for(&status(429, Some(3600)), 1),
+            Duration::from_secs(5)
+        );
+
+        let jittered = RetryPolicy {
+            jitter: 0.5,
+            ..policy
+        };
+        let delay = jittered.backoff(2);
+        assert!(delay > Duration::from_millis(100) && delay <= Duration::from_millis(200));
+    }
+}
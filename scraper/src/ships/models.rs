@@ -1,16 +1,27 @@
 use serde::Serialize;
 
-use crate::types::{NamedJpEn, NamedUrl, UrlTyped};
+use super::types::ShipStatusInfo;
+use crate::types::{FirstAppearance, NamedJpEn, NamedUrl, Sea, UrlTyped};
 
 #[derive(Debug, Default, Serialize)]
 pub struct Ship {
     pub name: String,
     pub en_name: String,
     pub description: String,
-    pub affiliation: NamedUrl,
-    pub status: String,
+    pub affiliation: Vec<NamedUrl>,
+    pub status: ShipStatusInfo,
+    pub ship_type: Option<String>,
+    pub builders: Vec<NamedUrl>,
+    pub captains: Vec<NamedUrl>,
+    pub length: Option<String>,
+    pub first_appearance: FirstAppearance,
+    pub origin: Option<NamedUrl>,
     pub pic_url: String,
     pub non_cannon: bool,
+    pub categories: Vec<String>,
+    pub seas: Vec<Sea>,
+    /// revision the ship was read from, only known with `--source api`
+    pub revision_id: Option<u64>,
     url: String,
 }
 
@@ -19,8 +30,8 @@ impl Ship {
         named_detail: NamedJpEn,
         url: String,
         pic_url: String,
-        affiliation: NamedUrl,
-        status: String,
+        affiliation: Vec<NamedUrl>,
+        status: ShipStatusInfo,
         non_cannon: bool,
     ) -> Self {
         Self {
@@ -29,11 +40,61 @@ impl Ship {
             description: named_detail.description,
             affiliation,
             status,
+            ship_type: None,
+            builders: vec![],
+            captains: vec![],
+            length: None,
+            first_appearance: FirstAppearance::default(),
+            origin: None,
             pic_url,
             url,
             non_cannon,
+            categories: vec![],
+            seas: vec![],
+            revision_id: None,
         }
     }
+
+    pub fn with_categories(mut self, categories: Vec<String>) -> Self {
+        self.seas = Sea::from_categories(&categories);
+        self.categories = categories;
+        self
+    }
+
+    pub fn with_type(mut self, ship_type: Option<String>) -> Self {
+        self.ship_type = ship_type;
+        self
+    }
+
+    pub fn with_builders(mut self, builders: Vec<NamedUrl>) -> Self {
+        self.builders = builders;
+        self
+    }
+
+    pub fn with_captains(mut self, captains: Vec<NamedUrl>) -> Self {
+        self.captains = captains;
+        self
+    }
+
+    pub fn with_length(mut self, length: Option<String>) -> Self {
+        self.length = length;
+        self
+    }
+
+    pub fn with_origin(mut self, origin: Option<NamedUrl>) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_first_appearance(mut self, first_appearance: FirstAppearance) -> Self {
+        self.first_appearance = first_appearance;
+        self
+    }
+
+    pub fn with_revision_id(mut self, revision_id: Option<u64>) -> Self {
+        self.revision_id = revision_id;
+        self
+    }
 }
 
 impl UrlTyped for Ship {
@@ -65,8 +126,12 @@ impl PartialOrd for Ship {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{NamedJpEn, NamedUrl};
+    use crate::{
+        ships::types::ShipStatus,
+        types::{NamedJpEn, NamedUrl},
+    };
 
+    #[allow(clippy::too_many_arguments)]
     fn create_test_ship(
         name: &str,
         en_name: &str,
@@ -75,7 +140,7 @@ mod tests {
         pic_url: &str,
         affiliation_name: &str,
         affiliation_url: &str,
-        status: &str,
+        status: ShipStatus,
     ) -> Ship {
         Ship::new(
             NamedJpEn::new(
@@ -85,8 +150,14 @@ mod tests {
             ),
             url.to_string(),
             pic_url.to_string(),
-            NamedUrl::new(affiliation_name.to_string(), affiliation_url.to_string()),
-            status.to_string(),
+            vec![NamedUrl::new(
+                affiliation_name.to_string(),
+                affiliation_url.to_string(),
+            )],
+            ShipStatusInfo {
+                status,
+                ..Default::default()
+            },
             false,
         )
     }
@@ -101,7 +172,7 @@ mod tests {
             "https://example.com/thousand-sunny.jpg",
             "Straw Hat Pirates",
             "crew/straw-hat-pirates",
-            "Active",
+            ShipStatus::Active,
         );
 
         assert_eq!(ship.get_path(), "ship/thousand-sunny");
@@ -120,7 +191,7 @@ mod tests {
             "",
             "Straw Hat Pirates",
             "crew/straw-hat-pirates",
-            "Active",
+            ShipStatus::Active,
         );
 
         let ship2 = create_test_ship(
@@ -131,7 +202,7 @@ mod tests {
             "",
             "Straw Hat Pirates",
             "crew/straw-hat-pirates",
-            "Inactive",
+            ShipStatus::Retired,
         );
 
         assert!(ship1 > ship2);
@@ -1,21 +1,24 @@
 use std::sync::Arc;
 
-use log::{error, info};
+use log::{error, info, warn};
 use scraper::Html;
-use tokio::{sync::Semaphore, task::JoinSet};
+use tokio::task::JoinSet;
 
 use crate::{
-    category::UrlCrawler,
+    category::{category_name, CrawlOptions, UrlCrawler},
     fetcher::HtmlFetcher,
-    types::{Error, NamedJpEn, NamedUrl},
+    mediawiki::{models::path_to_title, WikiSource},
+    types::{Error, FirstAppearance, NamedJpEn, ParseContext},
     utils,
 };
 
-use super::models::Ship;
+use super::{models::Ship, types::ShipStatusInfo};
 
 pub struct ShipScraper {
     fetcher: HtmlFetcher,
     category_crawler: Arc<dyn UrlCrawler>,
+    crawl_options: CrawlOptions,
+    page_source: Option<Arc<dyn WikiSource>>,
 }
 
 impl ShipScraper {
@@ -23,25 +26,39 @@ impl ShipScraper {
         Self {
             fetcher,
             category_crawler,
+            crawl_options: CrawlOptions::default(),
+            page_source: None,
         }
     }
 
+    pub fn with_crawl_options(mut self, options: CrawlOptions) -> Self {
+        self.crawl_options = options;
+        self
+    }
+
+    /// Read ship pages from `page_source` instead of fetching the rendered wiki.
+    pub fn with_page_source(mut self, page_source: Arc<dyn WikiSource>) -> Self {
+        self.page_source = Some(page_source);
+        self
+    }
+
     pub async fn scrape(&self) -> Result<Vec<Ship>, Error> {
         info!("crawling ship categories");
-        let urls = self
+        let membership = self
             .category_crawler
-            .get_nested_href("/wiki/Category:Ships", true)
+            .get_nested_href("/wiki/Category:Ships", &self.crawl_options, true)
             .await?;
 
-        let concurrency_limit = Arc::new(Semaphore::new(20));
         let mut ships = vec![];
         let mut ship_tasks = JoinSet::new();
-        for url in urls {
-            let permit = concurrency_limit.clone().acquire_owned().await.unwrap();
+        for (url, categories) in membership {
             let fetcher = self.fetcher.clone();
+            let page_source = self.page_source.clone();
+            let categories = categories.iter().map(|c| category_name(c)).collect();
             ship_tasks.spawn(async move {
-                let _permit = permit; // keep permit alive during task
-                let result = parse_ship_detail(fetcher, url.clone()).await;
+                let result = parse_ship_detail(fetcher, page_source, url.clone())
+                    .await
+                    .map(|ship| ship.with_categories(categories));
                 (url, result)
             });
         }
@@ -51,6 +68,9 @@ impl ShipScraper {
                 Ok((_, Ok(ship))) => {
                     ships.push(ship);
                 }
+                Ok((url, Err(e))) if e.is_not_found() => {
+                    warn!("Ship page {} no longer exists: {}", url, e)
+                }
                 Ok((url, Err(e))) => error!("Error parsing ship detail at {}: {}", url, e),
                 Err(e) => error!("JoinSet error {}", e),
             }
@@ -60,19 +80,37 @@ impl ShipScraper {
     }
 }
 
-async fn parse_ship_detail(fetcher: HtmlFetcher, ship_url: String) -> Result<Ship, Error> {
-    let html = fetcher.fetch(&ship_url).await.map(utils::cleanup_html)?;
-    let doc = Html::parse_document(&html);
-    let pic_url = utils::parse_picture_url(&doc)?
+async fn parse_ship_detail(
+    fetcher: HtmlFetcher,
+    page_source: Option<Arc<dyn WikiSource>>,
+    ship_url: String,
+) -> Result<Ship, Error> {
+    let (html, revision_id) = match page_source {
+        Some(source) => {
+            let page = source.page(&path_to_title(&ship_url)).await?;
+            (page.to_document(), Some(page.revision_id))
+        }
+        None => (fetcher.fetch(&ship_url).await?, None),
+    };
+    let doc = Html::parse_document(&utils::cleanup_html(html));
+    let pic_url = utils::parse_picture_url(&doc)
+        .parse_field(&ship_url, "picture")?
         .first()
         .cloned()
         .unwrap_or_default();
-    let en_name = utils::parse_main_page_title(&doc)?;
-    let description = utils::parse_main_page_first_paragraph(&doc)?;
-    let non_cannon = utils::parse_is_non_cannon(&doc)?;
+    let en_name = utils::parse_main_page_title(&doc).parse_field(&ship_url, "title")?;
+    let description =
+        utils::parse_main_page_first_paragraph(&doc).parse_field(&ship_url, "description")?;
+    let non_cannon = utils::parse_is_non_cannon(&doc).parse_field(&ship_url, "categories")?;
     let mut name_detail = NamedJpEn::new(String::new(), en_name, description);
-    let mut status = String::new();
-    let mut affiliation = NamedUrl::default();
+    let mut status = ShipStatusInfo::default();
+    let mut affiliation = vec![];
+    let mut ship_type = None;
+    let mut builders = vec![];
+    let mut captains = vec![];
+    let mut length = None;
+    let mut origin = None;
+    let mut first_appearance = FirstAppearance::default();
     let stat_selector = utils::parse_selector("aside.portable-infobox>section .pi-data")?;
     for el in doc.select(&stat_selector) {
         if let Some(kind) = el.attr("data-source") {
@@ -82,13 +120,32 @@ async fn parse_ship_detail(fetcher: HtmlFetcher, ship_url: String) -> Result<Shi
                         utils::parse_infobox_single_data_text(&el).unwrap_or_default();
                 }
                 "status" => {
-                    status = utils::parse_infobox_single_data_text(&el).unwrap_or_default();
+                    // older statuses are annotated as former, `(lost)` or `(sunk)` notes are not
+                    let entries = utils::parse_infobox_entries(&el);
+                    let current = entries
+                        .iter()
+                        .find(|e| !ShipStatusInfo::is_past(&e.annotations))
+                        .or(entries.first());
+                    if let Some(entry) = current {
+                        status = ShipStatusInfo::parse(&entry.text, &entry.annotations);
+                    }
+                }
+                "affiliation" | "affiliations" => {
+                    affiliation.extend(utils::parse_infobox_links(&el))
+                }
+                "type" | "ship type" | "class" => ship_type = utils::parse_infobox_text(&el),
+                "builder" | "builders" | "shipwright" | "shipwrights" => {
+                    builders.extend(utils::parse_infobox_links(&el))
                 }
-                "affiliation" => {
-                    affiliation = utils::parse_infobox_single_data_named_urls(&el)
-                        .first()
-                        .cloned()
-                        .unwrap_or_default();
+                "captain" | "captains" | "owner" | "owners" => {
+                    captains.extend(utils::parse_infobox_links(&el))
+                }
+                "origin" => origin = utils::parse_infobox_links(&el).into_iter().next(),
+                "length" => length = utils::parse_infobox_text(&el),
+                "first" => {
+                    first_appearance = utils::parse_first_appearance(
+                        &utils::parse_infobox_text(&el).unwrap_or_default(),
+                    )
                 }
                 _ => {}
             }
@@ -101,17 +158,48 @@ async fn parse_ship_detail(fetcher: HtmlFetcher, ship_url: String) -> Result<Shi
         affiliation,
         status,
         non_cannon,
-    ))
+    )
+    .with_type(ship_type)
+    .with_builders(builders)
+    .with_captains(captains)
+    .with_length(length)
+    .with_origin(origin)
+    .with_first_appearance(first_appearance)
+    .with_revision_id(revision_id))
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
+    use async_trait::async_trait;
+
     use crate::{
-        category::CategoryScraper, fetcher::mocks::prepare_fetcher, ships::scraper::ShipScraper,
+        category::CategoryScraper,
+        fetcher::mocks::prepare_fetcher,
+        mediawiki::{models::WikiPage, WikiSource},
+        ships::{scraper::ShipScraper, types::ShipStatus},
+        types::Error,
     };
 
+    /// serves a single parsed page, as `action=parse` would
+    struct SinglePage(WikiPage);
+
+    #[async_trait]
+    impl WikiSource for SinglePage {
+        async fn page(&self, title: &str) -> Result<WikiPage, Error> {
+            assert_eq!(title, self.0.title);
+            Ok(self.0.clone())
+        }
+
+        async fn category_members(
+            &self,
+            _category: &str,
+        ) -> Result<Vec<crate::mediawiki::models::CategoryMember>, Error> {
+            unimplemented!()
+        }
+    }
+
     #[tokio::test]
     async fn test_get() {
         let fetcher = prepare_fetcher([
@@ -167,7 +255,19 @@ mod tests {
                     <div class="pi-data-value pi-font"><a href="/wiki/Fallen_Monk_Pirates" title="Fallen Monk Pirates">Fallen Monk Pirates</a></div>
                 </div>
                 <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="status">
-                    <div class="pi-data-value pi-font">Active</div>
+                    <div class="pi-data-value pi-font">Active (formerly)<br>Destroyed (lost at Onigashima)</div>
+                </div>
+                <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="type">
+                    <div class="pi-data-value pi-font">Junk ship</div>
+                </div>
+                <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="captain">
+                    <div class="pi-data-value pi-font"><a href="/wiki/Urouge">Urouge</a><br><a href="/wiki/Some_Monk">Some Monk</a> (formerly)</div>
+                </div>
+                <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="length">
+                    <div class="pi-data-value pi-font">30 meters</div>
+                </div>
+                <div class="pi-item pi-data pi-item-spacing pi-border-color" data-source="first">
+                    <div class="pi-data-value pi-font">Chapter 498; Episode 392</div>
                 </div>
             </section>
         </aside>
@@ -219,5 +319,56 @@ mod tests {
         // validate non-cannon/cannon
         assert!(!ships[0].non_cannon);
         assert!(ships[1].non_cannon);
+        assert_eq!(ships[0].affiliation[0].name, "Fallen Monk Pirates");
+        assert_eq!(ships[0].ship_type.as_deref(), Some("Junk ship"));
+        assert_eq!(ships[0].captains.len(), 2);
+        assert!(ships[0].builders.is_empty());
+        assert_eq!(ships[0].length.as_deref(), Some("30 meters"));
+        assert_eq!(ships[0].first_appearance.chapter, Some(498));
+        assert_eq!(ships[0].first_appearance.episode, Some(392));
+        assert_eq!(ships[1].ship_type, None);
+        assert_eq!(ships[0].status.status, ShipStatus::Destroyed);
+        assert_eq!(
+            ships[0].status.reason.as_deref(),
+            Some("lost at Onigashima")
+        );
+        assert_eq!(ships[1].status.status, ShipStatus::Unknown);
+        assert_eq!(ships[1].status.raw, "Unknown");
+    }
+
+    #[tokio::test]
+    async fn pages_from_a_wiki_source() {
+        let fetcher = prepare_fetcher([(
+            "/wiki/Category:Ships".to_string(),
+            Ok(r##"<ul><li class="category-page__member"><a href="/wiki/Going_Merry" class="category-page__member-link">Going Merry</a></li></ul>"##.to_string()),
+        )]);
+        let page = WikiPage {
+            title: "Going Merry".to_string(),
+            page_id: 1505,
+            revision_id: 987654,
+            html: r##"<div class="mw-parser-output">
+                <aside class="portable-infobox"><section>
+                    <div class="pi-item pi-data" data-source="status"><div class="pi-data-value">Destroyed</div></div>
+                </section></aside>
+                <p></p><p></p><p>The first ship of the Straw Hat Pirates.</p>
+            </div>"##
+                .to_string(),
+            categories: vec!["Non-Canon".to_string()],
+        };
+        let crawler = Arc::new(CategoryScraper::new(fetcher.clone()));
+        let ships = ShipScraper::new(fetcher, crawler)
+            .with_page_source(Arc::new(SinglePage(page)))
+            .scrape()
+            .await
+            .unwrap();
+        assert_eq!(ships.len(), 1);
+        assert_eq!(ships[0].en_name, "Going Merry");
+        assert_eq!(
+            ships[0].description,
+            "The first ship of the Straw Hat Pirates."
+        );
+        assert_eq!(ships[0].status.status, ShipStatus::Destroyed);
+        assert!(ships[0].non_cannon);
+        assert_eq!(ships[0].revision_id, Some(987654));
     }
 }
@@ -0,0 +1,109 @@
+use serde::Serialize;
+use strum::{Display, EnumString};
+
+use crate::utils;
+
+#[derive(Debug, Clone, Copy, EnumString, PartialEq, Display, Serialize, Eq, Default)]
+#[strum(ascii_case_insensitive)]
+pub enum ShipStatus {
+    #[strum(to_string = "Active", serialize = "Operational")]
+    Active,
+    #[strum(to_string = "Destroyed", serialize = "Wrecked")]
+    Destroyed,
+    Sunk,
+    #[strum(
+        to_string = "Retired",
+        serialize = "Inactive",
+        serialize = "Decommissioned"
+    )]
+    Retired,
+    #[default]
+    Unknown,
+}
+
+/// Status of a ship, parsed from infobox text such as `Destroyed (Chapter 435)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ShipStatusInfo {
+    pub status: ShipStatus,
+    /// what happened to the ship, e.g. `burned by the Straw Hats`
+    pub reason: Option<String>,
+    pub chapter: Option<u32>,
+    /// infobox text, kept as is when the status is not recognised
+    pub raw: String,
+}
+
+impl ShipStatusInfo {
+    /// the status entry is a past one, e.g. `Active (formerly)`. Notes such as `(lost at sea)`
+    /// describe the current status instead.
+    pub fn is_past(annotations: &[String]) -> bool {
+        annotations.iter().any(|a| {
+            let a = a.to_lowercase();
+            a.contains("former") || a.contains("previous")
+        })
+    }
+
+    /// `text` is the part before any parentheses, `annotations` the parenthesized notes
+    pub fn parse(text: &str, annotations: &[String]) -> Self {
+        let raw = std::iter::once(text.to_string())
+            .chain(annotations.iter().map(|a| format!("({})", a)))
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let chapter = utils::parse_first_appearance(&raw).chapter;
+        let mut words = text.split_whitespace();
+        let status = words
+            .next()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+            .and_then(|w| w.parse().ok());
+        let Some(status) = status else {
+            return Self {
+                status: ShipStatus::Unknown,
+                reason: None,
+                chapter,
+                raw,
+            };
+        };
+        let rest = words.collect::<Vec<_>>().join(" ");
+        let reason = std::iter::once(rest.as_str())
+            .chain(annotations.iter().map(String::as_str))
+            .filter(|t| !t.is_empty() && utils::parse_first_appearance(t).chapter.is_none())
+            .collect::<Vec<_>>()
+            .join("; ");
+        Self {
+            status,
+            reason: Some(reason).filter(|r| !r.is_empty()),
+            chapter,
+            raw,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ShipStatus, ShipStatusInfo};
+
+    #[test]
+    fn status_with_reason_and_chapter() {
+        let info = ShipStatusInfo::parse("Destroyed", &["Chapter 435".to_string()]);
+        assert_eq!(info.status, ShipStatus::Destroyed);
+        assert_eq!(info.chapter, Some(435));
+        assert_eq!(info.reason, None);
+        assert_eq!(info.raw, "Destroyed (Chapter 435)");
+
+        let info = ShipStatusInfo::parse("Sunk by Aokiji", &["Chapter 1000".to_string()]);
+        assert_eq!(info.status, ShipStatus::Sunk);
+        assert_eq!(info.reason.as_deref(), Some("by Aokiji"));
+
+        assert_eq!(
+            ShipStatusInfo::parse("inactive", &[]).status,
+            ShipStatus::Retired
+        );
+
+        assert!(ShipStatusInfo::is_past(&["Formerly".to_string()]));
+        assert!(!ShipStatusInfo::is_past(&["lost at sea".to_string()]));
+
+        let info = ShipStatusInfo::parse("Stolen by Buggy", &[]);
+        assert_eq!(info.status, ShipStatus::Unknown);
+        assert_eq!(info.raw, "Stolen by Buggy");
+    }
+}
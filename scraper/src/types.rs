@@ -1,4 +1,5 @@
 use serde::Serialize;
+use strum::{Display, EnumIter, IntoEnumIterator};
 use thiserror::Error;
 
 pub trait UrlTyped {
@@ -8,9 +9,73 @@ pub trait UrlTyped {
 #[derive(Debug, Clone, Error)]
 pub enum Error {
     #[error("Request error: {0}")]
-    RequestError(String),
+    Request(String),
+    #[error("HTTP status {status} on url: {url}")]
+    HttpStatus {
+        url: String,
+        status: u16,
+        /// seconds from the `Retry-After` header, if the server sent one
+        retry_after: Option<u64>,
+    },
+    #[error("Request timed out on url: {0}")]
+    Timeout(String),
+    #[error("Failed to decode response body of {url}: {reason}")]
+    Decode { url: String, reason: String },
     #[error("Invalid html structure: {0}")]
     InvalidStructure(String),
+    #[error("Failed to parse `{field}` on {url}: {reason}")]
+    Parse {
+        url: String,
+        field: String,
+        reason: String,
+    },
+    #[error("Invalid table cell at row '{row}', column '{column}': {reason}")]
+    TableCell {
+        row: String,
+        column: String,
+        reason: String,
+    },
+    #[error("IO error: {0}")]
+    Io(String),
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+}
+
+impl Error {
+    /// the page does not exist (anymore), as opposed to a page whose layout changed
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            Error::HttpStatus {
+                status: 404 | 410,
+                ..
+            }
+        )
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e.to_string())
+    }
+}
+
+pub trait ParseContext<T> {
+    /// turns an [`Error::InvalidStructure`] into an [`Error::Parse`] of `field` on `url`
+    fn parse_field(self, url: &str, field: &str) -> Result<T, Error>;
+}
+
+impl<T> ParseContext<T> for Result<T, Error> {
+    fn parse_field(self, url: &str, field: &str) -> Result<T, Error> {
+        self.map_err(|e| match e {
+            Error::InvalidStructure(reason) => Error::Parse {
+                url: url.to_string(),
+                field: field.to_string(),
+                reason,
+            },
+            other => other,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Default)]
@@ -47,3 +112,64 @@ impl NamedJpEn {
         }
     }
 }
+
+/// Chapter and episode in which something first appeared.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FirstAppearance {
+    pub chapter: Option<u32>,
+    pub episode: Option<u32>,
+    /// non-canon media have neither chapter nor episode
+    pub raw: String,
+}
+
+/// Seas of the world, as named by the wiki categories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumIter, Serialize)]
+pub enum Sea {
+    #[strum(serialize = "East Blue")]
+    EastBlue,
+    #[strum(serialize = "West Blue")]
+    WestBlue,
+    #[strum(serialize = "North Blue")]
+    NorthBlue,
+    #[strum(serialize = "South Blue")]
+    SouthBlue,
+    #[strum(serialize = "Grand Line")]
+    GrandLine,
+    Paradise,
+    #[strum(serialize = "New World")]
+    NewWorld,
+}
+
+impl Sea {
+    /// Every sea named by one of `categories`, e.g. `Grand Line Pirate Crews`.
+    ///
+    /// Pages keep the names of the categories they are directly listed under, as found by the
+    /// category crawl, and the seas derived from them here.
+    pub fn from_categories(categories: &[String]) -> Vec<Sea> {
+        Sea::iter()
+            .filter(|sea| {
+                let name = sea.to_string();
+                categories.iter().any(|c| c.contains(&name))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sea;
+
+    #[test]
+    fn seas_from_categories() {
+        let categories = vec![
+            "Non-Canon Grand Line Pirate Crews".to_string(),
+            "New World Pirate Crews".to_string(),
+            "Pirate Groups".to_string(),
+        ];
+        assert_eq!(
+            Sea::from_categories(&categories),
+            vec![Sea::GrandLine, Sea::NewWorld]
+        );
+        assert!(Sea::from_categories(&["Marine Ships".to_string()]).is_empty());
+    }
+}
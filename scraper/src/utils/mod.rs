@@ -1,2 +1,4 @@
 pub mod parser;
-pub use parser::*;
+pub mod table;
+pub(crate) use parser::*;
+pub(crate) use table::*;
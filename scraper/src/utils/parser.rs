@@ -3,10 +3,13 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use scraper::{ElementRef, Html, Selector};
 
-use crate::types::{Error, NamedUrl};
+use crate::types::{Error, FirstAppearance, NamedUrl, UrlTyped};
 
 lazy_static! {
     static ref REX_SUP: Regex = Regex::new(r"<sup.*?>.*?</sup>").unwrap();
+    static ref REX_CHAPTER: Regex = Regex::new(r"(?i)chapter\s+(\d+)").unwrap();
+    static ref REX_EPISODE: Regex = Regex::new(r"(?i)episode\s+(\d+)").unwrap();
+    static ref REX_BERRIES: Regex = Regex::new(r"\d[\d,.]*").unwrap();
 }
 
 pub(crate) fn cleanup_html(html_source: String) -> String {
@@ -37,6 +40,17 @@ pub(crate) fn extract_all_href(el: &ElementRef, selector: &str) -> Result<Vec<St
         .collect())
 }
 
+/// path and query of an absolute link, relative links are returned as they are
+pub(crate) fn to_url_path(href: &str) -> String {
+    match reqwest::Url::parse(href) {
+        Ok(url) => match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        },
+        Err(_) => href.to_string(),
+    }
+}
+
 pub(crate) async fn get_first_parents_sibling_text(
     html_doc: &Html,
     selector: &str,
@@ -50,7 +64,7 @@ pub(crate) async fn get_first_parents_sibling_text(
             "invalid sibling node",
         )))?
         .find(|n| n.value().is_element())
-        .and_then(|n| ElementRef::wrap(n))
+        .and_then(ElementRef::wrap)
         .map(|e| e.text().join(""))
         .ok_or(Error::InvalidStructure(String::from("invalid element")))
 }
@@ -72,7 +86,7 @@ pub(crate) fn parse_main_page_title(html_doc: &Html) -> Result<String, Error> {
         .map(|e| e.text())
         .and_then(|mut t| t.next())
         .map(String::from)
-        .ok_or(Error::InvalidStructure(format!(
+        .ok_or(Error::InvalidStructure(String::from(
             "invalid title page element",
         )))
 }
@@ -83,8 +97,8 @@ pub(crate) fn parse_main_page_first_paragraph(html_doc: &Html) -> Result<String,
         .select(&parse_selector("main #mw-content-text p:nth-of-type(3)")?)
         .next()
         .map(|e| e.text().join("").replace("\n", ""))
-        .ok_or(Error::InvalidStructure(format!(
-            "invalid first paragraph element"
+        .ok_or(Error::InvalidStructure(String::from(
+            "invalid first paragraph element",
         )))
 }
 
@@ -112,3 +126,303 @@ pub(crate) fn parse_is_non_cannon(html_doc: &Html) -> Result<bool, Error> {
         .any(|url| url.contains("Category:Non-Canon"));
     Ok(non_cannon)
 }
+
+pub(crate) fn parse_infobox_data<'a>(html_doc: &'a Html, source: &str) -> Option<ElementRef<'a>> {
+    let selector = format!(
+        "aside.portable-infobox .pi-data[data-source=\"{}\"]",
+        source
+    );
+    html_doc.select(&parse_selector(&selector).ok()?).next()
+}
+
+/// One value of a multi-valued infobox field, e.g. `Going Merry (formerly; destroyed)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct InfoboxEntry {
+    /// first link outside of the parentheses
+    pub link: Option<NamedUrl>,
+    pub text: String,
+    /// parenthesized notes, one per `;` or `,` separated part
+    pub annotations: Vec<String>,
+}
+
+impl InfoboxEntry {
+    /// annotated as no longer being the case, e.g. `(formerly)` or `(destroyed)`
+    pub fn is_former(&self) -> bool {
+        const FORMER: [&str; 7] = [
+            "former",
+            "previous",
+            "retired",
+            "destroyed",
+            "sunk",
+            "lost",
+            "abandoned",
+        ];
+        self.annotations.iter().any(|a| {
+            let a = a.to_lowercase();
+            FORMER.iter().any(|f| a.contains(f))
+        })
+    }
+}
+
+/// splits the text of a field into entries and their parenthesized annotations
+#[derive(Default)]
+struct EntryBuilder<'a> {
+    entries: Vec<InfoboxEntry>,
+    current: InfoboxEntry,
+    annotation: String,
+    depth: usize,
+    last_link: Option<ElementRef<'a>>,
+}
+
+impl<'a> EntryBuilder<'a> {
+    fn push_text(&mut self, text: &str, link: Option<ElementRef<'a>>) {
+        if let Some(a) = link.filter(|_| self.depth == 0 && link != self.last_link) {
+            // every link outside of the parentheses starts a new entry
+            if self.current.link.is_some() {
+                self.finish();
+            }
+            self.current.link = a
+                .value()
+                .attr("href")
+                .map(|url| NamedUrl::new(a.text().join("").trim().to_string(), url.to_string()));
+        }
+        self.last_link = link;
+        for c in text.chars() {
+            match c {
+                '(' => {
+                    if self.depth > 0 {
+                        self.annotation.push(c);
+                    }
+                    self.depth += 1;
+                }
+                ')' if self.depth > 0 => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        self.push_annotation();
+                    } else {
+                        self.annotation.push(c);
+                    }
+                }
+                _ if self.depth > 0 => self.annotation.push(c),
+                _ => self.current.text.push(c),
+            }
+        }
+    }
+
+    fn push_annotation(&mut self) {
+        let annotation = std::mem::take(&mut self.annotation);
+        self.current.annotations.extend(
+            annotation
+                .split([';', ','])
+                .map(|a| a.split_whitespace().join(" "))
+                .filter(|a| !a.is_empty()),
+        );
+    }
+
+    fn finish(&mut self) {
+        self.push_annotation();
+        self.depth = 0;
+        self.last_link = None;
+        let mut entry = std::mem::take(&mut self.current);
+        entry.text = entry.text.split_whitespace().join(" ");
+        if entry.link.is_some() || !entry.text.is_empty() {
+            self.entries.push(entry);
+        }
+    }
+}
+
+/// Values of a multi-valued infobox field, split on `<br>` and list items.
+pub(crate) fn parse_infobox_entries(el: &ElementRef) -> Vec<InfoboxEntry> {
+    let value = el
+        .select(&parse_selector(".pi-data-value").unwrap())
+        .next()
+        .unwrap_or(*el);
+    let li_selector = parse_selector("li").unwrap();
+    let items = value.select(&li_selector).collect_vec();
+    let blocks = if items.is_empty() { vec![value] } else { items };
+    let mut builder = EntryBuilder::default();
+    for block in blocks {
+        for node in block.descendants() {
+            let inner = node
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .take_while(|e| *e != block)
+                .collect_vec();
+            if inner.iter().any(|e| e.value().name() == "li") {
+                // nested lists are items of their own
+                continue;
+            }
+            if node.value().as_element().is_some_and(|e| e.name() == "br") {
+                builder.finish();
+            } else if let Some(text) = node.value().as_text() {
+                let link = inner
+                    .into_iter()
+                    .find(|e| e.value().name() == "a" && e.value().attr("href").is_some());
+                builder.push_text(text, link);
+            }
+        }
+        builder.finish();
+    }
+    builder.entries
+}
+
+pub(crate) fn parse_infobox_links(el: &ElementRef) -> Vec<NamedUrl> {
+    parse_infobox_entries(el)
+        .into_iter()
+        .filter_map(|entry| entry.link)
+        .unique_by(|link| link.get_path())
+        .collect()
+}
+
+pub(crate) fn parse_infobox_text(el: &ElementRef) -> Option<String> {
+    parse_infobox_single_data_text(el)
+        .map(|t| t.split_whitespace().join(" "))
+        .filter(|t| !t.is_empty())
+}
+
+/// first amount of berries in `text`, e.g. `3,189,000,000 (formerly 1,500,000,000)`
+pub(crate) fn parse_berries(text: &str) -> Option<u64> {
+    REX_BERRIES
+        .find(text)?
+        .as_str()
+        .replace([',', '.'], "")
+        .parse()
+        .ok()
+}
+
+/// e.g. `Chapter 1; Episode 4`
+pub(crate) fn parse_first_appearance(text: &str) -> FirstAppearance {
+    let number = |rex: &Regex| {
+        rex.captures(text)
+            .and_then(|c| c.get(1))
+            .and_then(|m| m.as_str().parse().ok())
+    };
+    FirstAppearance {
+        chapter: number(&REX_CHAPTER),
+        episode: number(&REX_EPISODE),
+        raw: text.trim().to_string(),
+    }
+}
+
+/// Elements of the section under the heading holding `id`, up to the next heading of the same or a higher level.
+pub(crate) fn parse_section_elements<'a>(
+    html_doc: &'a Html,
+    id: &str,
+) -> Option<Vec<ElementRef<'a>>> {
+    let anchor = html_doc
+        .select(&parse_selector(&format!("#{}", id)).ok()?)
+        .next()?;
+    let mut heading = std::iter::once(anchor)
+        .chain(anchor.ancestors().filter_map(ElementRef::wrap))
+        .find(|e| heading_level(e).is_some())?;
+    // newer skins wrap headings in a div, the section content follows the wrapper
+    if let Some(wrapper) = heading
+        .parent()
+        .and_then(ElementRef::wrap)
+        .filter(|p| heading_level(p).is_some())
+    {
+        heading = wrapper;
+    }
+    let level = heading_level(&heading)?;
+    Some(
+        heading
+            .next_siblings()
+            .filter_map(ElementRef::wrap)
+            .take_while(|e| heading_level(e).is_none_or(|l| l > level))
+            .collect(),
+    )
+}
+
+/// Text of the paragraphs and lists of a section, see [`parse_section_elements`].
+pub(crate) fn parse_section_text(html_doc: &Html, id: &str) -> Option<String> {
+    section_text(parse_section_elements(html_doc, id)?)
+}
+
+/// Text of a section up to its first subheading, see [`parse_section_text`].
+pub(crate) fn parse_section_lead_text(html_doc: &Html, id: &str) -> Option<String> {
+    section_text(
+        parse_section_elements(html_doc, id)?
+            .into_iter()
+            .take_while(|e| heading_level(e).is_none()),
+    )
+}
+
+fn section_text<'a>(elements: impl IntoIterator<Item = ElementRef<'a>>) -> Option<String> {
+    let text = elements
+        .into_iter()
+        .filter(|e| matches!(e.value().name(), "p" | "ul" | "ol" | "dl"))
+        .map(|e| e.text().join("").trim().to_string())
+        .filter(|t| !t.is_empty())
+        .join("\n");
+    Some(text).filter(|t| !t.is_empty())
+}
+
+/// level of a `h1`-`h6` heading, or of a `div.mw-heading` wrapping one
+fn heading_level(el: &ElementRef) -> Option<u8> {
+    let name = el.value().name();
+    if name == "div" && el.value().classes().any(|c| c == "mw-heading") {
+        return el.child_elements().find_map(|e| heading_level(&e));
+    }
+    match name.strip_prefix('h')?.parse() {
+        Ok(level @ 1..=6) => Some(level),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::{Html, Selector};
+
+    use super::{parse_berries, parse_infobox_entries};
+
+    #[test]
+    fn berries() {
+        assert_eq!(parse_berries("5,564,800,000"), Some(5_564_800_000));
+        assert_eq!(
+            parse_berries("At least 3.189.000.000 (formerly 1,500,000,000)"),
+            Some(3_189_000_000)
+        );
+        assert_eq!(parse_berries("Unknown"), None);
+    }
+
+    #[test]
+    fn infobox_entries_with_annotations() {
+        let doc = Html::parse_fragment(
+            r#"<div class="pi-data" data-source="ship"><div class="pi-data-value">
+            <a href="/wiki/Going_Merry">Going Merry</a> (<a href="/wiki/Former">formerly</a>; destroyed)<br>
+            <a href="/wiki/Thousand_Sunny"><i>Thousand</i> Sunny</a><br>
+            Unnamed raft (formerly)
+            </div></div>"#,
+        );
+        let el = doc
+            .select(&Selector::parse(".pi-data").unwrap())
+            .next()
+            .unwrap();
+        let entries = parse_infobox_entries(&el);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].link.as_ref().unwrap().name, "Going Merry");
+        assert_eq!(entries[0].annotations, vec!["formerly", "destroyed"]);
+        assert!(entries[0].is_former());
+        assert_eq!(entries[1].link.as_ref().unwrap().name, "Thousand Sunny");
+        assert!(!entries[1].is_former());
+        assert_eq!(entries[2].link, None);
+        assert_eq!(entries[2].text, "Unnamed raft");
+
+        let doc = Html::parse_fragment(
+            r#"<div class="pi-data"><ul>
+            <li><a href="/wiki/Oro_Jackson">Oro Jackson</a> (retired)</li>
+            <li><a href="/wiki/Red_Force">Red Force</a></li>
+            </ul></div>"#,
+        );
+        let el = doc
+            .select(&Selector::parse(".pi-data").unwrap())
+            .next()
+            .unwrap();
+        let entries = parse_infobox_entries(&el);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].annotations, vec!["retired"]);
+        assert!(entries[0].is_former());
+        assert_eq!(entries[1].link.as_ref().unwrap().name, "Red Force");
+        assert!(!entries[1].is_former());
+    }
+}
@@ -0,0 +1,180 @@
+use std::str::FromStr;
+
+use itertools::Itertools as _;
+use scraper::{ElementRef, Html};
+
+use crate::types::Error;
+
+use super::parse_selector;
+
+/// A wiki table read through its header row, so columns are looked up by name instead of position.
+#[derive(Debug)]
+pub(crate) struct Table<'a> {
+    headers: Vec<String>,
+    rows: Vec<TableRow<'a>>,
+}
+
+/// A data row, the first cell is its label.
+#[derive(Debug)]
+pub(crate) struct TableRow<'a> {
+    cells: Vec<ElementRef<'a>>,
+}
+
+/// whitespace normalized text of a cell, footnotes left out
+fn cell_text(cell: &ElementRef) -> String {
+    cell.descendants()
+        .filter(|n| {
+            !n.ancestors()
+                .any(|a| a.value().as_element().is_some_and(|e| e.name() == "sup"))
+        })
+        .filter_map(|n| n.value().as_text().map(|t| t.to_string()))
+        .join(" ")
+        .split_whitespace()
+        .join(" ")
+}
+
+impl<'a> Table<'a> {
+    /// First `table.wikitable` of `html_doc` having every column of `columns`.
+    pub fn find(html_doc: &'a Html, columns: &[&str]) -> Result<Self, Error> {
+        html_doc
+            .select(&parse_selector("table.wikitable")?)
+            // unrelated tables may have any layout, only the wanted one has to parse
+            .filter_map(|t| Table::from_element(t).ok())
+            .find(|table| columns.iter().all(|c| table.column(c).is_ok()))
+            .ok_or(Error::InvalidStructure(format!(
+                "no table with columns {:?}",
+                columns
+            )))
+    }
+
+    pub fn from_element(table: ElementRef<'a>) -> Result<Self, Error> {
+        let row_selector = parse_selector("tr")?;
+        let cell_selector = parse_selector("th, td")?;
+        let mut headers = None;
+        let mut rows = vec![];
+        for tr in table.select(&row_selector) {
+            let cells = tr.select(&cell_selector).collect_vec();
+            if headers.is_none() {
+                // the header is the first row made of th only
+                if !cells.is_empty() && cells.iter().all(|c| c.value().name() == "th") {
+                    headers = Some(cells.iter().map(cell_text).collect());
+                }
+                continue;
+            }
+            if cells.iter().any(|c| c.value().name() == "td") {
+                rows.push(TableRow { cells });
+            }
+        }
+        Ok(Self {
+            headers: headers.ok_or(Error::InvalidStructure(String::from(
+                "table without header row",
+            )))?,
+            rows,
+        })
+    }
+
+    pub fn column(&self, name: &str) -> Result<usize, Error> {
+        self.headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+            .ok_or(Error::InvalidStructure(format!(
+                "missing column '{}' in {:?}",
+                name, self.headers
+            )))
+    }
+
+    pub fn rows(&self) -> &[TableRow<'a>] {
+        &self.rows
+    }
+}
+
+impl<'a> TableRow<'a> {
+    pub fn label(&self) -> String {
+        self.cells.first().map(cell_text).unwrap_or_default()
+    }
+
+    pub fn element(&self, column: usize) -> Option<ElementRef<'a>> {
+        self.cells.get(column).copied()
+    }
+
+    pub fn text(&self, column: usize) -> Option<String> {
+        self.element(column).map(|c| cell_text(&c))
+    }
+
+    /// Number in `column`, thousands separators allowed. `name` is only used in the error.
+    pub fn number<T: FromStr>(&self, column: usize, name: &str) -> Result<T, Error> {
+        let error = |reason: String| Error::TableCell {
+            row: self.label(),
+            column: name.to_string(),
+            reason,
+        };
+        let text = self
+            .text(column)
+            .ok_or_else(|| error(String::from("missing cell")))?;
+        text.replace(',', "")
+            .parse()
+            .map_err(|_| error(format!("'{}' is not a number", text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::Html;
+
+    use crate::types::Error;
+
+    use super::Table;
+
+    const TABLE: &str = r#"
+        <table class="wikitable"><tbody>
+            <tr><th>Rank</th></tr>
+            <tr><td>Admiral</td></tr>
+        </table>
+        <table class="wikitable"><tbody>
+            <tr><th></th><th>Canon</th><th>Non-Canon <sup>[1]</sup></th><th>Total</th></tr>
+            <tr><th>Paramecia</th><td>94</td><td>48</td><td>1,042</td></tr>
+            <tr><th>Zoan</th><td></td><td>seven</td></tr>
+        </tbody></table>"#;
+
+    #[test]
+    fn columns_by_header_name() {
+        let doc = Html::parse_document(TABLE);
+        let table = Table::find(&doc, &["Canon", "non-canon"]).unwrap();
+        let canon = table.column("Canon").unwrap();
+        let total = table.column("Total").unwrap();
+        assert_eq!((canon, total), (1, 3));
+        assert!(table.column("Manga").is_err());
+
+        let rows = table.rows();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].label(), "Paramecia");
+        assert_eq!(rows[0].number::<u32>(canon, "Canon").unwrap(), 94);
+        assert_eq!(rows[0].number::<u32>(total, "Total").unwrap(), 1042);
+
+        assert!(matches!(
+            rows[1].number::<u32>(canon, "Canon"),
+            Err(Error::TableCell { row, column, .. }) if row == "Zoan" && column == "Canon"
+        ));
+        assert!(rows[1].number::<u32>(2, "Non-Canon").is_err());
+        assert!(rows[1].number::<u32>(total, "Total").is_err());
+
+        assert!(Table::find(&doc, &["Canon", "Movies"]).is_err());
+    }
+
+    #[test]
+    fn skips_malformed_tables() {
+        let doc = Html::parse_document(
+            r#"
+            <table class="wikitable"><tbody>
+                <tr><td>Navigation</td><td>without a header row</td></tr>
+            </tbody></table>
+            <table class="wikitable"><tbody>
+                <tr><th></th><th>Canon</th><th>Non-Canon</th><th>Total</th></tr>
+                <tr><td>Logia</td><td>13</td><td>3</td><td>16</td></tr>
+            </tbody></table>"#,
+        );
+        let table = Table::find(&doc, &["Canon", "Non-Canon", "Total"]).unwrap();
+        assert_eq!(table.rows()[0].label(), "Logia");
+        assert!(Table::find(&doc, &["Navigation"]).is_err());
+    }
+}